@@ -1,5 +1,6 @@
 //pub mod luma;
 pub mod rgb;
+pub mod rgba;
 
 //enum of supported color types
 #[derive(Copy, PartialEq, Eq, Debug, Clone, Hash)]
@@ -11,8 +12,8 @@ pub enum ColorType {
     // La8,
     /// Pixel contains 8-bit R, G and B channels
     Rgb8,
-    // /// Pixel is 8-bit RGB with an alpha channel
-    // Rgba8,
+    /// Pixel is 8-bit RGB with an alpha channel
+    Rgba8,
     // /// Pixel is 16-bit luminance
     // L16,
     // /// Pixel is 16-bit luminance with an alpha channel
@@ -34,6 +35,7 @@ impl ColorType {
         match self {
             ColorType::L8 => 1,
             ColorType::Rgb8 => 3,
+            ColorType::Rgba8 => 4,
         }
     }
 
@@ -41,6 +43,7 @@ impl ColorType {
     pub fn has_alpha(self) -> bool {
         match self {
             ColorType::L8 | ColorType::Rgb8 => false,
+            ColorType::Rgba8 => true,
         }
     }
 
@@ -49,6 +52,7 @@ impl ColorType {
         match self {
             ColorType::L8 => 1,
             ColorType::Rgb8 => 3,
+            ColorType::Rgba8 => 4,
         }
     }
 }
@@ -135,13 +135,15 @@ impl<T: PixelComponent> Rem for RGB<T> {
 }
 
 impl<T: PixelComponent> EuclidianDistance for RGB<T> {
-    fn dist_euclidian(&self, _other: &Self) -> i32 {
-        todo!();
-        // let r_diff: i32 = i32::from(self.0) - i32::from(other.0);
-        // let g_diff: i32 = i32::from(self.1) - i32::from(other.1);
-        // let b_diff: i32 = i32::from(self.2) - i32::from(other.2);
-        //
-        // return (r_diff * r_diff) + (g_diff * g_diff) + (b_diff * b_diff);
+    fn dist_euclidian(&self, other: &Self) -> i32 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| {
+                let diff = a.to_i32().unwrap_or(i32::MAX) - b.to_i32().unwrap_or(i32::MAX);
+                diff * diff
+            })
+            .sum()
     }
 }
 
@@ -0,0 +1,190 @@
+use std::{
+    fmt::{Display, LowerHex},
+    ops::{Add, Div, Index, IndexMut, Mul, Rem, Sub},
+};
+
+use crate::pixel::{EuclidianDistance, Pixel, PixelComponent};
+
+// ##### DEFINITIONS #####
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct RGBA<T: PixelComponent>(pub [T; 4]);
+impl<T: PixelComponent> Pixel for RGBA<T> {
+    type Subpixel = T;
+
+    const CHANNEL_COUNT: u8 = 4;
+
+    #[inline(always)]
+    fn channels(&self) -> &[Self::Subpixel] {
+        &self.0
+    }
+
+    #[inline(always)]
+    fn channels_mut(&mut self) -> &mut [Self::Subpixel] {
+        &mut self.0
+    }
+
+    fn from_slice(slice: &[Self::Subpixel]) -> &Self {
+        assert_eq!(slice.len(), usize::from(Self::CHANNEL_COUNT));
+        unsafe { &*(slice.as_ptr() as *const RGBA<T>) }
+    }
+
+    fn from_slice_mut(slice: &mut [Self::Subpixel]) -> &mut RGBA<T> {
+        assert_eq!(slice.len(), usize::from(Self::CHANNEL_COUNT));
+
+        unsafe { &mut *(slice.as_mut_ptr() as *mut RGBA<T>) }
+    }
+
+    fn map_with_alpha<F, G>(&self, mut f: F, mut g: G) -> Self
+    where
+        F: FnMut(Self::Subpixel) -> Self::Subpixel,
+        G: FnMut(Self::Subpixel) -> Self::Subpixel,
+    {
+        let mut this = *self;
+
+        for v in this.0[..3].iter_mut() {
+            *v = f(*v)
+        }
+        this.0[3] = g(this.0[3]);
+        this
+    }
+
+    const DEFAULT_MAX_VALUE: Self = Self([
+        T::DEFAULT_MAX_VALUE,
+        T::DEFAULT_MAX_VALUE,
+        T::DEFAULT_MAX_VALUE,
+        T::DEFAULT_MAX_VALUE,
+    ]);
+
+    const DEFAULT_MIN_VALUE: Self = Self([
+        T::DEFAULT_MIN_VALUE,
+        T::DEFAULT_MIN_VALUE,
+        T::DEFAULT_MIN_VALUE,
+        T::DEFAULT_MIN_VALUE,
+    ]);
+}
+
+impl<T: Default + PixelComponent> Default for RGBA<T> {
+    fn default() -> Self {
+        Self([T::default(), T::default(), T::default(), T::default()])
+    }
+}
+
+impl<T: PixelComponent> Add for RGBA<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        RGBA([
+            self.0[0] + rhs.0[0],
+            self.0[1] + rhs.0[1],
+            self.0[2] + rhs.0[2],
+            self.0[3] + rhs.0[3],
+        ])
+    }
+}
+
+impl<T: PixelComponent> Sub for RGBA<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        RGBA([
+            self.0[0] - rhs.0[0],
+            self.0[1] - rhs.0[1],
+            self.0[2] - rhs.0[2],
+            self.0[3] - rhs.0[3],
+        ])
+    }
+}
+
+impl<T: PixelComponent> Mul for RGBA<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        RGBA([
+            self.0[0] * rhs.0[0],
+            self.0[1] * rhs.0[1],
+            self.0[2] * rhs.0[2],
+            self.0[3] * rhs.0[3],
+        ])
+    }
+}
+
+impl<T: PixelComponent> Div for RGBA<T> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        RGBA([
+            self.0[0] / rhs.0[0],
+            self.0[1] / rhs.0[1],
+            self.0[2] / rhs.0[2],
+            self.0[3] / rhs.0[3],
+        ])
+    }
+}
+
+impl<T: PixelComponent> Rem for RGBA<T> {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        RGBA([
+            self.0[0] % rhs.0[0],
+            self.0[1] % rhs.0[1],
+            self.0[2] % rhs.0[2],
+            self.0[3] % rhs.0[3],
+        ])
+    }
+}
+
+impl<T: PixelComponent> EuclidianDistance for RGBA<T> {
+    fn dist_euclidian(&self, other: &Self) -> i32 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| {
+                let diff = a.to_i32().unwrap_or(i32::MAX) - b.to_i32().unwrap_or(i32::MAX);
+                diff * diff
+            })
+            .sum()
+    }
+}
+
+impl<T: PixelComponent> Index<usize> for RGBA<T> {
+    type Output = T;
+    #[inline(always)]
+    fn index(&self, i: usize) -> &T {
+        &self.0[i]
+    }
+}
+impl<T: PixelComponent> IndexMut<usize> for RGBA<T> {
+    #[inline(always)]
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        &mut self.0[i]
+    }
+}
+
+impl<T: PixelComponent> From<[T; 4]> for RGBA<T> {
+    fn from(c: [T; 4]) -> Self {
+        Self(c)
+    }
+}
+
+impl<T: PixelComponent> From<&[T; 4]> for RGBA<T> {
+    fn from(c: &[T; 4]) -> Self {
+        Self([c[0], c[1], c[2], c[3]])
+    }
+}
+
+impl<T: PixelComponent + LowerHex> Display for RGBA<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:x}", self)
+    }
+}
+
+impl<T: PixelComponent + LowerHex> LowerHex for RGBA<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.0[0], self.0[1], self.0[2], self.0[3]
+        )
+    }
+}
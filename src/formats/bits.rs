@@ -333,8 +333,28 @@ impl<W: Write> BitWriter for LittleEndianWriter<W> {
         // now add the remaining to the buffer
     }
 
-    fn write_n<U: Primitive>(&mut self, _data: U, bits: u8) -> io::Result<()> {
+    fn write_n<U: Primitive>(&mut self, data: U, bits: u8) -> io::Result<()> {
         assert!(bits <= U::BITS);
+
+        // negative values are written in their two's complement representation
+        let mut value = data
+            .to_u128()
+            .or_else(|| data.to_i128().map(|value| value as u128))
+            .ok_or_else(|| io::Error::other("cannot convert value into bits"))?;
+        let mut bits = bits;
+
+        while bits > 0 {
+            // fill the buffer with as many bits as fit into it
+            let taken = bits.min(u8::BITS as u8 - self.pos);
+            let mask = (1u128 << taken) - 1;
+            self.buf[0] |= ((value & mask) as u8) << self.pos;
+            self.pos += taken;
+            if self.pos == 8 {
+                self.write_out_internal()?;
+            }
+            value >>= taken;
+            bits -= taken;
+        }
         Ok(())
     }
 }
@@ -453,4 +473,40 @@ mod tests {
 
         assert_eq!(data, [0b1101_1011, 0b1111_1100]);
     }
+    #[test]
+    fn test_little_endian_writer_write_n() {
+        let mut data: Vec<u8> = Vec::new();
+        let mut bit_writer = LittleEndianWriter::new(&mut data);
+
+        bit_writer.write_n(0b101u8, 3).unwrap();
+        bit_writer.write_n(0b1_0110_0111u16, 9).unwrap();
+        bit_writer.write_n(0b1111u32, 4).unwrap();
+        bit_writer.flush().unwrap();
+
+        assert_eq!(data, [0b0011_1101, 0b1111_1011]);
+    }
+    #[test]
+    fn test_little_endian_write_n_read_n() {
+        let values = [
+            (3u16, 2u8),
+            (0, 3),
+            (4095, 12),
+            (17, 5),
+            (511, 9),
+            (1, 1),
+            (300, 12),
+        ];
+
+        let mut data: Vec<u8> = Vec::new();
+        let mut bit_writer = LittleEndianWriter::new(&mut data);
+        for (value, bits) in values {
+            bit_writer.write_n(value, bits).unwrap();
+        }
+        bit_writer.flush().unwrap();
+
+        let mut bit_reader = LittleEndianReader::new(&data[..]);
+        for (value, bits) in values {
+            assert_result_eq(bit_reader.read_n::<u16>(bits), value);
+        }
+    }
 }
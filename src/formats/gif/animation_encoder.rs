@@ -0,0 +1,323 @@
+//! Encodes a sequence of full images into an animated gif.
+//!
+//! Every frame is given as the complete image that should be shown. The encoder keeps track of
+//! what a decoder shows after each frame (using the `Compositor`) so it can crop frames to the
+//! area that actually changed.
+
+use std::{collections::HashMap, io::Write};
+
+use crate::{colors::rgb::RGB, generic_image::GenericImage, image_buffer::ImageBuffer};
+
+use super::{
+    blocks::{
+        ApplicationExtension, ColorTable, ColorTableLookup, CommentExtension, MAX_COLOR_TABLE_SIZE,
+    },
+    compositor::Compositor,
+    encoding::GIFEncoder,
+    errors::GIFEncodeError,
+    gif::{DisposalMethod, GIFFrame, LoopCount},
+};
+
+/// A full logical screen image together with its timing.
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub image: ImageBuffer<RGB<u8>, Vec<u8>>,
+    /// Time in hundredths of a second to wait before continuing with the next frame
+    pub delay_time: u16,
+    pub disposal_method: DisposalMethod,
+}
+
+/// Writes animation frames with a global palette.
+///
+/// Frames using colors that are not in the palette get a local color table if they have at most
+/// 256 distinct colors, otherwise the colors are mapped to the nearest color of the palette.
+pub struct AnimationEncoder<W: Write> {
+    encoder: GIFEncoder<W>,
+    logical_dim: (u16, u16),
+    palette: ColorTable,
+    /// Color to index of the palette, for the exact lookup of colors
+    palette_indices: HashMap<RGB<u8>, u8>,
+    loop_count: Option<LoopCount>,
+    crop: bool,
+    /// The canvas as a decoder shows it, `None` before the first frame
+    compositor: Option<Compositor>,
+    /// Whether the last frame was restored to the background, see `write_frame`
+    restored_background: bool,
+}
+
+impl<W: Write> AnimationEncoder<W> {
+    /// Creates an encoder for an animation of `logical_dim` using `palette` as global color
+    /// table. The first color of the palette is the background color.
+    pub fn new(writer: W, logical_dim: (u16, u16), palette: ColorTable) -> Self {
+        let mut palette_indices = HashMap::new();
+        for index in (0..palette.size.min(MAX_COLOR_TABLE_SIZE)).rev() {
+            palette_indices.insert(palette.data[index], index as u8);
+        }
+
+        AnimationEncoder {
+            encoder: GIFEncoder::new(writer, logical_dim, Some(palette)),
+            logical_dim,
+            palette,
+            palette_indices,
+            loop_count: Some(LoopCount::Infinite),
+            crop: true,
+            compositor: None,
+            restored_background: false,
+        }
+    }
+
+    /// How often the animation is played. `None` omits the looping extension, most viewers then
+    /// show the animation only once. Defaults to looping forever.
+    pub fn with_loop_count(mut self, loop_count: Option<LoopCount>) -> Self {
+        self.loop_count = loop_count;
+        self
+    }
+
+    /// Whether frames are cropped to the rectangle that changed since the previous frame.
+    /// Enabled by default.
+    pub fn with_cropping(mut self, crop: bool) -> Self {
+        self.crop = crop;
+        self
+    }
+
+    /// Writes the next frame. The image must have the size of the logical screen.
+    pub fn write_frame(&mut self, frame: &AnimationFrame) -> Result<(), GIFEncodeError> {
+        let (width, height) = frame.image.dimensions();
+        if (width, height) != (self.logical_dim.0 as u32, self.logical_dim.1 as u32) {
+            return Err(GIFEncodeError::InvalidFrameData);
+        }
+
+        let first_frame = self.compositor.is_none();
+        let compositor = match &mut self.compositor {
+            Some(compositor) => compositor,
+            None => {
+                if let Some(loop_count) = self.loop_count {
+                    self.encoder
+                        .write_application_extension(&ApplicationExtension::looping(loop_count))?;
+                }
+                let background = self.palette.lookup_fallback(0);
+                self.compositor
+                    .insert(Compositor::new(self.logical_dim, background))
+            }
+        };
+        compositor.dispose();
+
+        // The first frame covers the whole screen. Viewers disagree on what restore to background
+        // means (the background color or transparency), so after such a frame the whole screen is
+        // written as well.
+        let rect = if self.crop && !first_frame && !self.restored_background {
+            changed_rect(compositor.canvas(), &frame.image)
+        } else {
+            (0, 0, width, height)
+        };
+
+        let (indices, color_table) =
+            index_rect(&self.palette, &self.palette_indices, &frame.image, rect);
+        let gif_frame = GIFFrame {
+            left: rect.0 as u16,
+            top: rect.1 as u16,
+            width: (rect.2 - rect.0) as u16,
+            height: (rect.3 - rect.1) as u16,
+            indices,
+            color_table,
+            delay_time: frame.delay_time,
+            disposal_method: frame.disposal_method,
+            user_input: false,
+            transparent_color_index: None,
+        };
+
+        self.encoder.write_frame(&gif_frame)?;
+        compositor.composite(&gif_frame);
+        self.restored_background = frame.disposal_method == DisposalMethod::RestoreBackground;
+        Ok(())
+    }
+
+    /// Writes a comment extension, e.g. a caption or the source of the animation. Comments can be
+    /// written before, between and after frames.
+    pub fn write_comment(&mut self, comment: &CommentExtension) -> Result<(), GIFEncodeError> {
+        self.encoder.write_comment(comment)
+    }
+
+    /// Writes the trailer and returns the underlying writer.
+    pub fn finish(self) -> Result<W, GIFEncodeError> {
+        self.encoder.finish()
+    }
+}
+
+/// Turns the rectangle (left, top, right, bottom) of `image` into color indices and the color
+/// table they refer to, preferring `palette` whose colors are indexed by `palette_indices`.
+fn index_rect(
+    palette: &ColorTable,
+    palette_indices: &HashMap<RGB<u8>, u8>,
+    image: &ImageBuffer<RGB<u8>, Vec<u8>>,
+    (left, top, right, bottom): (u32, u32, u32, u32),
+) -> (Vec<u8>, ColorTable) {
+    let colors = (top..bottom)
+        .flat_map(|y| (left..right).map(move |x| *image.get_pixel(x, y)))
+        .collect::<Vec<_>>();
+
+    if colors
+        .iter()
+        .all(|color| palette_indices.contains_key(color))
+    {
+        let indices = colors.iter().map(|color| palette_indices[color]).collect();
+        return (indices, *palette);
+    }
+
+    // try a local color table with the exact colors
+    let mut local = ColorTable::from_colors(&[]);
+    let mut local_indices: HashMap<RGB<u8>, u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(colors.len());
+    for color in &colors {
+        let index = match local_indices.get(color) {
+            Some(index) => *index,
+            None if local.size < MAX_COLOR_TABLE_SIZE => {
+                local.data[local.size] = *color;
+                local_indices.insert(*color, local.size as u8);
+                local.size += 1;
+                (local.size - 1) as u8
+            }
+            None => {
+                // too many colors, fall back to the nearest colors of the palette
+                let indices = colors
+                    .iter()
+                    .map(|color| palette.nearest_index(*color))
+                    .collect();
+                return (indices, *palette);
+            }
+        };
+        indices.push(index);
+    }
+    (indices, local)
+}
+
+/// The bounding rectangle (left, top, right, bottom) of all pixels that differ between `canvas`
+/// and `image`. If nothing changed a single pixel is returned, as a frame cannot be empty.
+fn changed_rect(
+    canvas: &ImageBuffer<RGB<u8>, Vec<u8>>,
+    image: &ImageBuffer<RGB<u8>, Vec<u8>>,
+) -> (u32, u32, u32, u32) {
+    let (width, height) = image.dimensions();
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+
+    for (x, y, pixel) in image.iter() {
+        if canvas.get_pixel(x, y) != pixel {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x + 1);
+            bottom = bottom.max(y + 1);
+        }
+    }
+
+    if left >= right {
+        return (0, 0, width.min(1), height.min(1));
+    }
+    (left, top, right, bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formats::gif::better_decoder::GIFDecoder, generic_image::GenericImageMut};
+
+    use super::*;
+
+    const BLACK: RGB<u8> = RGB([0, 0, 0]);
+    const WHITE: RGB<u8> = RGB([255, 255, 255]);
+    const RED: RGB<u8> = RGB([255, 0, 0]);
+
+    fn palette() -> ColorTable {
+        ColorTable::from_colors(&[WHITE, BLACK])
+    }
+
+    fn image(pixels: &[(u32, u32, RGB<u8>)]) -> ImageBuffer<RGB<u8>, Vec<u8>> {
+        let mut image = ImageBuffer::new(8, 6);
+        for y in 0..6 {
+            for x in 0..8 {
+                image.put_pixel(x, y, WHITE);
+            }
+        }
+        for (x, y, color) in pixels {
+            image.put_pixel(*x, *y, *color);
+        }
+        image
+    }
+
+    fn frame(image: ImageBuffer<RGB<u8>, Vec<u8>>, delay_time: u16) -> AnimationFrame {
+        AnimationFrame {
+            image,
+            delay_time,
+            disposal_method: DisposalMethod::DoNotDispose,
+        }
+    }
+
+    #[test]
+    fn encode_animation() {
+        let frames = [
+            frame(image(&[]), 10),
+            frame(image(&[(2, 1, BLACK), (4, 3, BLACK)]), 20),
+            // a color outside of the palette needs a local color table
+            frame(image(&[(2, 1, BLACK), (4, 3, RED)]), 30),
+            // nothing changed
+            frame(image(&[(2, 1, BLACK), (4, 3, RED)]), 40),
+        ];
+
+        let mut encoder = AnimationEncoder::new(Vec::new(), (8, 6), palette())
+            .with_loop_count(Some(LoopCount::Repeat(2)));
+        encoder
+            .write_comment(&CommentExtension::new("animation encoder test"))
+            .unwrap();
+        for frame in &frames {
+            encoder.write_frame(frame).unwrap();
+        }
+        let data = encoder.finish().unwrap();
+
+        let animation = GIFDecoder::new(&data[..]).decode_frames().unwrap();
+        assert_eq!(animation.loop_count, Some(LoopCount::Repeat(2)));
+        assert_eq!(
+            animation.comments[0].text().unwrap(),
+            "animation encoder test"
+        );
+
+        let decoded = &animation.frames;
+        assert_eq!(decoded.len(), 4);
+        assert_eq!((decoded[0].width, decoded[0].height), (8, 6));
+        // cropped to the changed pixels
+        assert_eq!((decoded[1].left, decoded[1].top), (2, 1));
+        assert_eq!((decoded[1].width, decoded[1].height), (3, 3));
+        assert_eq!((decoded[2].left, decoded[2].top), (4, 3));
+        assert_eq!((decoded[2].width, decoded[2].height), (1, 1));
+        assert_eq!(decoded[2].color_table.lookup(0), Some(RED));
+        assert_eq!((decoded[3].width, decoded[3].height), (1, 1));
+
+        let delays = decoded.iter().map(|f| f.delay_time).collect::<Vec<_>>();
+        assert_eq!(delays, [10, 20, 30, 40]);
+
+        for (canvas, expected) in animation.composite_frames().iter().zip(&frames) {
+            assert_eq!(canvas.as_container(), expected.image.as_container());
+        }
+    }
+
+    #[test]
+    fn no_cropping_after_restore_background() {
+        let mut first = frame(image(&[(0, 0, BLACK)]), 0);
+        first.disposal_method = DisposalMethod::RestoreBackground;
+        let second = frame(image(&[(1, 1, BLACK)]), 0);
+
+        let mut encoder =
+            AnimationEncoder::new(Vec::new(), (8, 6), palette()).with_loop_count(None);
+        encoder.write_frame(&first).unwrap();
+        encoder.write_frame(&second).unwrap();
+        let data = encoder.finish().unwrap();
+
+        let animation = GIFDecoder::new(&data[..]).decode_frames().unwrap();
+        assert_eq!(animation.loop_count, None);
+        assert_eq!(
+            animation.frames[0].disposal_method,
+            DisposalMethod::RestoreBackground
+        );
+        assert_eq!(
+            (animation.frames[1].width, animation.frames[1].height),
+            (8, 6)
+        );
+    }
+}
@@ -1,12 +1,16 @@
-// Decodes a *valid* gif byte stream into one or more images.
+// Decodes a gif byte stream into one or more images.
+// By default decoding is strict and fails on the first invalid block. In lenient mode (see
+// `GIFDecoder::with_lenient`) damaged image data and a truncated stream are recovered from
+// with warnings.
 
-use std::{error::Error, io::Read};
+use std::io::{self, Read};
 
 use crate::{
     colors::rgb::RGB,
-    formats::gif::blocks::GraphicControlExtension,
-    generic_image::{GenericImage, GenericImageMut},
-    image_buffer::ImageBuffer,
+    formats::gif::blocks::{
+        ApplicationExtension, CommentExtension, GraphicControlExtension, PlainTextExtension,
+        decoding::skip_subblock,
+    },
 };
 
 use super::{
@@ -15,9 +19,11 @@ use super::{
         GraphicRenderingBlocks, Header, LogicalScreenDescriptor, SpecialPurposeBlocks,
         TableBasedImage,
     },
-    errors::GIFParseError,
-    gif::{DisposalMethod, GIFDecode, Version},
-    ringbuf::RingBuffer,
+    compositor::Compositor,
+    errors::{DecodeWarning, GIFParseError},
+    gif::{GIFDecode, GIFFrame, GIFImage, LoopCount, MultiGIF, SingleGIF, Version},
+    limits::{DecoderLimits, Limit},
+    plain_text::render_plain_text,
 };
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -127,28 +133,45 @@ struct GIFDecoderState {
     logical_dim: (u16, u16),
     color_resolution: u8,
     pixel_aspect_ration: u8,
-    background_color_ration: u8,
+    background_color_index: u8,
 
     /// The point in which we are in the grammar parsing of the stream
     grammar_state: ReadNext,
-    /// The last and the current GraphicControlExtension as a "ring buffer"
-    graphic_controls: RingBuffer<GraphicControlExtension, 2>,
+    /// The GraphicControlExtension read since the last graphic rendering block. It only applies to
+    /// the graphic rendering block directly following it.
+    active_graphic_control: Option<GraphicControlExtension>,
 }
 
 //TODO: Enable switching the decompression.
 
-struct GIFDecoder<'a, R: Read + Copy> {
+pub struct GIFDecoder<'a, R: Read> {
     /// The state of the current decoding
     state: GIFDecoderState,
     /// A reference to the default color table to use. !Should not be changed after initialization
     default_color_table: Option<&'a ColorTable>,
-    current_image: Option<ImageBuffer<RGB<u8>, Vec<u8>>>,
-    is_animation: bool,
+    /// The frame completed by the last state transition, if any
+    decoded_frame: Option<GIFFrame>,
+    loop_count: Option<LoopCount>,
+    /// Application extensions the decoder does not interpret itself
+    application_extensions: Vec<ApplicationExtension>,
+    comments: Vec<CommentExtension>,
+    limits: DecoderLimits,
+    /// The number of frames decoded so far
+    frame_count: usize,
+    /// The number of color indices of all frames decoded so far
+    decoded_bytes: usize,
+    /// Whether all frames are kept in memory, only then the totals of all frames are limited
+    keep_frames: bool,
+    /// Whether to recover from broken data instead of failing, see `with_lenient`
+    lenient: bool,
+    warnings: Vec<DecodeWarning>,
     reader: R,
 }
 
-impl<R: Read + Copy> GIFDecoder<'_, R> {
-    fn new(reader: R) -> Self {
+impl<R: Read> GIFDecoder<'_, R> {
+    /// Creates a decoder reading from `reader`. The decoder reads in many small chunks, so readers
+    /// without internal buffering (e.g. files or sockets) should be wrapped in a `BufReader`.
+    pub fn new(reader: R) -> Self {
         GIFDecoder {
             state: GIFDecoderState {
                 global_color_table: None,
@@ -156,25 +179,108 @@ impl<R: Read + Copy> GIFDecoder<'_, R> {
                 logical_dim: (0, 0),
                 color_resolution: 0,
                 pixel_aspect_ration: 0,
-                background_color_ration: 0,
+                background_color_index: 0,
                 grammar_state: ReadNext::Header,
-                graphic_controls: RingBuffer::new(),
+                active_graphic_control: None,
             },
             default_color_table: None,
-            current_image: None,
-            is_animation: false,
+            decoded_frame: None,
+            loop_count: None,
+            application_extensions: Vec::new(),
+            comments: Vec::new(),
+            limits: DecoderLimits::default(),
+            frame_count: 0,
+            decoded_bytes: 0,
+            keep_frames: false,
+            lenient: false,
+            warnings: Vec::new(),
             reader,
         }
     }
 
+    /// Enables lenient decoding, which recovers from common defects of gifs in the wild instead
+    /// of failing:
+    ///
+    /// - a missing trailer or a broken block ends the data stream, keeping all frames before it
+    /// - truncated image data or invalid lzw codes keep the pixels decoded up to that point and
+    ///   fill the rest of the frame with the background color
+    ///
+    /// Every recovery is recorded as a warning. Errors before the first frame and exceeding the
+    /// limits still fail.
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Replaces the default `DecoderLimits`. Must be called before the first frame is read.
+    pub fn with_limits(mut self, limits: DecoderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn check_dimensions(&self, (width, height): (u16, u16)) -> Result<(), GIFParseError> {
+        if width > self.limits.max_width || height > self.limits.max_height {
+            return Err(GIFParseError::LimitExceeded(Limit::Dimensions));
+        }
+        Ok(())
+    }
+
+    /// Checks the size of a frame before its color indices are allocated.
+    fn check_frame_size(&self, (width, height): (u16, u16)) -> Result<(), GIFParseError> {
+        self.check_dimensions((width, height))?;
+        if width as usize * height as usize > self.limits.max_frame_pixels {
+            return Err(GIFParseError::LimitExceeded(Limit::FramePixels));
+        }
+        Ok(())
+    }
+
+    /// In lenient mode ends the data stream at `err` with a warning, if at least one frame was
+    /// decoded before. Otherwise returns the error.
+    fn recover(&mut self, err: GIFParseError) -> Result<(), GIFParseError> {
+        let recoverable =
+            self.lenient && !matches!(err, GIFParseError::LimitExceeded(_)) && self.frame_count > 0;
+        if !recoverable {
+            return Err(err);
+        }
+
+        let warning = match &err {
+            GIFParseError::Io { cause, .. }
+                if self.state.grammar_state == ReadNext::BlockType(None)
+                    && cause.kind() == io::ErrorKind::UnexpectedEof =>
+            {
+                DecodeWarning::MissingTrailer
+            }
+            _ => DecodeWarning::UnexpectedEnd {
+                reason: err.to_string(),
+            },
+        };
+        self.warnings.push(warning);
+        Ok(())
+    }
+
+    /// Accounts for a new frame of `pixel_count` color indices before it is decoded. The number
+    /// of frames and their total size are only limited if the frames are kept.
+    fn count_frame(&mut self, pixel_count: usize) -> Result<(), GIFParseError> {
+        let decoded_bytes = self.decoded_bytes.saturating_add(pixel_count);
+        if self.keep_frames {
+            if self.frame_count >= self.limits.max_frames {
+                return Err(GIFParseError::LimitExceeded(Limit::FrameCount));
+            }
+            if decoded_bytes > self.limits.max_decoded_bytes {
+                return Err(GIFParseError::LimitExceeded(Limit::DecodedBytes));
+            }
+        }
+
+        self.frame_count += 1;
+        self.decoded_bytes = decoded_bytes;
+        Ok(())
+    }
+
     fn next_state(&mut self) -> Result<ReadNext, GIFParseError> {
         match self.state.grammar_state {
             ReadNext::Header => {
                 let header = Header::parse(&mut self.reader)?;
                 self.state.version = header.version;
-
-                println!("HEADER: {:#?}", header);
-
                 Ok(ReadNext::LogicalScreenDescriptor)
             }
             ReadNext::LogicalScreenDescriptor => {
@@ -185,9 +291,10 @@ impl<R: Read + Copy> GIFDecoder<'_, R> {
                     descriptor.logical_screen_width,
                     descriptor.logical_screen_height,
                 );
+                // the logical screen is allocated by the compositor
+                self.check_dimensions(self.state.logical_dim)?;
                 self.state.color_resolution = descriptor.color_resolution();
-
-                println!("LogicalScreenDescriptor: {:#?}", descriptor);
+                self.state.background_color_index = descriptor.background_color_index;
 
                 if descriptor.global_color_table_flag() {
                     Ok(ReadNext::GlobalColorTable(
@@ -201,7 +308,6 @@ impl<R: Read + Copy> GIFDecoder<'_, R> {
             ReadNext::GlobalColorTable(size, sorted) => {
                 let table = ColorTable::try_from_reader(&mut self.reader, size, sorted)?;
                 self.state.global_color_table = Some(table);
-                println!("Global Color Table : {}", table);
                 Ok(ReadNext::BlockType(None))
             }
             ReadNext::BlockType(restriction) => {
@@ -218,7 +324,7 @@ impl<R: Read + Copy> GIFDecoder<'_, R> {
                     .ok_or(GIFParseError::UnexpectedBlockDiscriminant(buf[0]))?;
 
                 if restriction
-                    .is_some_and(|restriction_type| separator.can_be_type(restriction_type))
+                    .is_some_and(|restriction_type| !separator.can_be_type(restriction_type))
                 {
                     return Err(GIFParseError::UnexpectedBlockDiscriminant(buf[0]));
                 }
@@ -278,34 +384,34 @@ impl<R: Read + Copy> GIFDecoder<'_, R> {
                     _ => Err(GIFParseError::UnexpectedExtensionLabel(buf[0])),
                 }
             }
-            ReadNext::ControlBlock(block_type) => {
-                match block_type {
-                    ControlBlocks::GraphicsControlExtension => {
-                        self.process_graphic_control_extension()?
-                    }
-                    ControlBlocks::UnknownBlock => panic!("unknown control block"),
+            ReadNext::ControlBlock(block_type) => match block_type {
+                ControlBlocks::GraphicsControlExtension => {
+                    self.process_graphic_control_extension()?;
+                    Ok(ReadNext::BlockType(Some(BlockLabelType::Graphic)))
                 }
-                Ok(ReadNext::BlockType(None))
-            }
+                ControlBlocks::UnknownBlock => {
+                    // unknown extensions must be skipped according to the gif spec
+                    skip_subblock(&mut self.reader)?;
+                    Ok(ReadNext::BlockType(None))
+                }
+            },
             ReadNext::GraphicsBlock(block_type) => {
                 match block_type {
                     GraphicRenderingBlocks::TableBasedImage => self.process_table_based_image()?,
                     GraphicRenderingBlocks::PlainTextExtension => {
-                        self.process_plain_text_extension()
+                        self.process_plain_text_extension()?
                     }
-                    GraphicRenderingBlocks::UnknownBlock => panic!("unknown graphic block"),
+                    GraphicRenderingBlocks::UnknownBlock => skip_subblock(&mut self.reader)?,
                 }
                 Ok(ReadNext::BlockType(None))
             }
-            ReadNext::SpecialPurposeBlock(_block_type) => {
-                match _block_type {
-                    SpecialPurposeBlocks::CommentExtension => self.process_comment_extension(),
+            ReadNext::SpecialPurposeBlock(block_type) => {
+                match block_type {
+                    SpecialPurposeBlocks::CommentExtension => self.process_comment_extension()?,
                     SpecialPurposeBlocks::ApplicationExtension => {
-                        self.process_application_extension()
-                    }
-                    SpecialPurposeBlocks::UnknownBlock => {
-                        panic!("unknown special purpose block extension")
+                        self.process_application_extension()?
                     }
+                    SpecialPurposeBlocks::UnknownBlock => skip_subblock(&mut self.reader)?,
                 }
                 Ok(ReadNext::BlockType(None))
             }
@@ -316,126 +422,286 @@ impl<R: Read + Copy> GIFDecoder<'_, R> {
     /// Processes a table based image i.e. an image descriptor (with Local Color Table) and then the
     /// (LZW Compressed) Image Data.
     ///
-    /// Then parses the image with the current color table and interlace flags to the
-    /// `self.current_image`.
-    ///
-    /// Returns an empty result, since all image data is stored in `self.current_image`.
+    /// The image is stored as `self.decoded_frame` together with the settings of the
+    /// graphic control extension preceding it (if any).
     fn process_table_based_image(&mut self) -> Result<(), GIFParseError> {
-        // What we have to do here heavily depends on the previous graphic control extension (if
-        // any)
-
-        // let active_control = self.state.active_graphic_control.unwrap_or_default();
-        //
-        // if active_control.delay_time() > 0 {
-        //     self.is_animation = true;
-        // }
-        //
-        // if active_control.delay_time() > 0 || self.current_image.is_none() {
-        //     // Either we must have a new frame (delay_time)
-        //     self.create_image(active_control.disposal_method());
-        // }
-
-        // Get the current image or create one if not already
-        if self.current_image.is_none() {
-            self.current_image = Some(ImageBuffer::<RGB<u8>, Vec<u8>>::new(
-                self.state.logical_dim.0 as u32,
-                self.state.logical_dim.1 as u32,
-            ))
-        }
-        let image = self.current_image.as_mut().unwrap();
+        // The graphic control extension is only valid for this graphic rendering block
+        let control = self.state.active_graphic_control.take().unwrap_or_default();
 
-        let table_based_image = TableBasedImage::parse(&mut self.reader)?;
+        // The limits are checked before the image data is decoded
+        let (descriptor, local_color_table) = TableBasedImage::parse_descriptor(&mut self.reader)?;
+        let (width, height) = descriptor.image_dim();
+        let pixel_count = width as usize * height as usize;
+        self.check_frame_size((width, height))?;
+        self.count_frame(pixel_count)?;
+        let frame = self.frame_count - 1;
+
+        let (table_based_image, data_error) =
+            TableBasedImage::parse_data(&mut self.reader, descriptor, local_color_table)?;
 
         // Get the color table Local > Global > Default
         let color_table = table_based_image
-            .local_color_table_ref()
-            .or(self.state.global_color_table.as_ref())
-            .or(self.default_color_table)
-            .unwrap();
+            .local_color_table()
+            .or(self.state.global_color_table)
+            .or(self.default_color_table.copied())
+            .unwrap_or_default();
+
+        if let Some(err) = data_error {
+            if !self.lenient {
+                return Err(err.into());
+            }
+            self.warnings.push(DecodeWarning::InvalidImageData {
+                frame,
+                reason: GIFParseError::from(err).to_string(),
+            });
+        }
 
-        if table_based_image.descriptor().interlace_flag() {
-            //draw interlaced
-        } else {
-            //draw continuous
-            // OPTIMIZE: this
-            let pixels = table_based_image
-                .data()
-                .iter()
-                .map(|color_index| color_table.lookup_fallback(*color_index))
-                .collect::<Vec<_>>();
-
-            if let Err(_e) = image.put_rect(
-                table_based_image.descriptor().image_position().left as u32,
-                table_based_image.descriptor().image_position().top as u32,
-                table_based_image.descriptor().image_dim().0 as u32,
-                table_based_image.descriptor().image_dim().1 as u32,
-                &pixels,
-            ) {
-                // pixels.len < width * height
+        let mut indices = table_based_image.into_data();
+        if indices.len() < pixel_count {
+            if !self.lenient {
                 return Err(GIFParseError::ImageDataError);
             }
+            self.warnings.push(DecodeWarning::MissingPixels {
+                frame,
+                count: pixel_count - indices.len(),
+            });
+            indices.resize(pixel_count, self.background_index_for(&color_table));
+        }
+        indices.truncate(pixel_count);
+
+        if descriptor.interlace_flag() {
+            indices = deinterlace(&indices, width as usize, height as usize);
         }
 
+        self.decoded_frame = Some(GIFFrame {
+            left: descriptor.image_position().left,
+            top: descriptor.image_position().top,
+            width,
+            height,
+            indices,
+            color_table,
+            delay_time: control.delay_time(),
+            disposal_method: control.disposal_method(),
+            user_input: control.user_input_flag(),
+            transparent_color_index: control.transparent_color_index(),
+        });
+
         Ok(())
     }
 
     fn process_graphic_control_extension(&mut self) -> Result<(), GIFParseError> {
         let block = GraphicControlExtension::parse(&mut self.reader)?;
+        self.state.active_graphic_control = Some(block);
+        Ok(())
+    }
+
+    /// Processes a plain text extension by rendering its text into a frame covering the text
+    /// grid. As the spec requires, the colors are taken from the global color table.
+    fn process_plain_text_extension(&mut self) -> Result<(), GIFParseError> {
+        // The plain text is a graphic rendering block and consumes the control extension as well.
+        let control = self.state.active_graphic_control.take().unwrap_or_default();
+
+        let extension = PlainTextExtension::parse(&mut self.reader)?;
+        let color_table = self
+            .state
+            .global_color_table
+            .or(self.default_color_table.copied())
+            .unwrap_or_default();
+
+        let (left, top) = extension.text_grid_position();
+        let (width, height) = extension.text_grid_dim();
+        self.check_frame_size((width, height))?;
+        self.count_frame(width as usize * height as usize)?;
+        self.decoded_frame = Some(GIFFrame {
+            left,
+            top,
+            width,
+            height,
+            indices: render_plain_text(&extension),
+            color_table,
+            delay_time: control.delay_time(),
+            disposal_method: control.disposal_method(),
+            user_input: control.user_input_flag(),
+            transparent_color_index: control.transparent_color_index(),
+        });
 
-        block.disposal_method();
-        todo!();
+        Ok(())
     }
 
-    fn process_plain_text_extension(&mut self) {
-        todo!();
+    fn process_comment_extension(&mut self) -> Result<(), GIFParseError> {
+        let comment = CommentExtension::parse(&mut self.reader)?;
+        self.comments.push(comment);
+        Ok(())
     }
 
-    fn process_comment_extension(&mut self) {
-        todo!();
+    fn process_application_extension(&mut self) -> Result<(), GIFParseError> {
+        let extension = ApplicationExtension::parse(&mut self.reader)?;
+        if extension.is_looping_extension() {
+            // a looping extension without the loop sub-block is ignored
+            if let Some(loop_count) = extension.loop_count() {
+                self.loop_count = Some(loop_count);
+            }
+        } else {
+            self.application_extensions.push(extension);
+        }
+        Ok(())
     }
-    fn process_application_extension(&mut self) {
-        todo!();
+
+    /// The size of the logical screen. Only valid once the first frame has been read.
+    pub fn logical_dim(&self) -> (u16, u16) {
+        self.state.logical_dim
+    }
+
+    /// The global color table, if there is one. Only valid once the first frame has been read.
+    pub fn global_color_table(&self) -> Option<&ColorTable> {
+        self.state.global_color_table.as_ref()
     }
-}
 
-impl<R: Read + Copy> GIFDecode for GIFDecoder<'_, R> {
-    fn decode(mut self) -> Result<super::gif::GIFImage, GIFParseError> {
-        self.state.grammar_state = ReadNext::Header;
+    /// The index of the background color in the global color table. Only valid once the first
+    /// frame has been read.
+    pub fn background_color_index(&self) -> u8 {
+        self.state.background_color_index
+    }
 
-        let seen_error = false;
-        // iterate through states
-        while !seen_error && self.state.grammar_state != ReadNext::End {
-            //TODO: debug print currently parsing state
+    /// The index of the background color in `color_table`: the background color index if it is
+    /// the global color table, otherwise the index of the color nearest to the background color.
+    pub fn background_index_for(&self, color_table: &ColorTable) -> u8 {
+        match self.global_color_table() {
+            Some(global) if global == color_table => self.background_color_index(),
+            _ => color_table.nearest_index(self.background_color()),
+        }
+    }
 
-            println!("Parsing State [{:?}]: started", self.state.grammar_state);
+    /// The background color looked up in the global color table (black if there is none). Only
+    /// valid once the first frame has been read.
+    pub fn background_color(&self) -> RGB<u8> {
+        self.state
+            .global_color_table
+            .map(|table| table.lookup_fallback(self.state.background_color_index))
+            .unwrap_or_default()
+    }
 
-            let res = self.next_state();
+    /// The loop count of the looping extension read so far, if any. Looping extensions usually
+    /// come before the first frame.
+    pub fn loop_count(&self) -> Option<LoopCount> {
+        self.loop_count
+    }
 
-            if let Err(err) = res {
-                //TODO: debug print
-                println!("Error during parse {}", err);
-                return Err(err);
-            }
+    /// The comments read so far. Comments may appear anywhere in the data stream, so all of them
+    /// are only known once the last frame has been read.
+    pub fn comments(&self) -> &[CommentExtension] {
+        &self.comments
+    }
 
-            match res {
-                Ok(read_next) => self.state.grammar_state = read_next,
+    /// The problems recovered from so far in lenient mode.
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        &self.warnings
+    }
+
+    /// Reads the data stream up to and including the next graphic rendering block (table based
+    /// image or plain text) and returns it as a frame. Returns `None` once the trailer has been read
+    /// or after an error.
+    pub fn next_frame(&mut self) -> Result<Option<GIFFrame>, GIFParseError> {
+        while self.state.grammar_state != ReadNext::End {
+            self.state.grammar_state = match self.next_state() {
+                Ok(state) => state,
                 Err(err) => {
-                    //TODO: debug print error
-                    return Err(err);
+                    let recovered = self.recover(err);
+                    // the data stream cannot be continued after an error, later calls end it
+                    self.state.grammar_state = ReadNext::End;
+                    recovered?;
+                    ReadNext::End
                 }
+            };
+
+            if let Some(frame) = self.decoded_frame.take() {
+                return Ok(Some(frame));
             }
+        }
+        Ok(None)
+    }
+
+    /// Decodes the whole data stream into its frames without compositing them onto the logical
+    /// screen. A gif with a single image results in a `MultiGIF` with a single frame.
+    pub fn decode_frames(mut self) -> Result<MultiGIF, GIFParseError> {
+        self.keep_frames = true;
+        let mut frames = Vec::new();
+        while let Some(frame) = self.next_frame()? {
+            frames.push(frame);
+        }
+
+        Ok(MultiGIF {
+            logical_dim: self.logical_dim(),
+            background_color_index: self.state.background_color_index,
+            background_color: self.background_color(),
+            frames,
+            loop_count: self.loop_count,
+            application_extensions: self.application_extensions,
+            comments: self.comments,
+            warnings: self.warnings,
+        })
+    }
+}
 
-            println!("Parsing State: done")
+/// The passes an interlaced image is stored in as (first row, row step)
+const INTERLACE_PASSES: [(usize, usize); 4] = [(0, 8), (4, 8), (2, 4), (1, 2)];
+
+/// Reorders the rows of an interlaced image into continuous top to bottom order.
+///
+/// Interlaced images store every 8th row starting at row 0, then every 8th row starting at row 4,
+/// then every 4th row starting at row 2 and finally every 2nd row starting at row 1.
+fn deinterlace(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+    if width == 0 {
+        return indices.to_vec();
+    }
+
+    let mut deinterlaced = vec![0u8; indices.len()];
+    let mut rows = indices.chunks_exact(width);
+    for (first_row, step) in INTERLACE_PASSES {
+        for y in (first_row..height).step_by(step) {
+            if let Some(row) = rows.next() {
+                deinterlaced[y * width..(y + 1) * width].copy_from_slice(row);
+            }
         }
+    }
+    deinterlaced
+}
 
-        println!("Parse GIF complete");
-        Ok(super::gif::GIFImage::Single(self.current_image.unwrap()))
+impl<R: Read> GIFDecode for GIFDecoder<'_, R> {
+    fn decode(self) -> Result<GIFImage, GIFParseError> {
+        let animation = self.decode_frames()?;
+        match animation.frames.len() {
+            0 => Ok(GIFImage::None),
+            1 => {
+                let mut compositor = Compositor::for_animation(&animation);
+                compositor.composite(&animation.frames[0]);
+                Ok(GIFImage::Single(SingleGIF {
+                    image: compositor.into_canvas(),
+                    application_extensions: animation.application_extensions,
+                    comments: animation.comments,
+                    warnings: animation.warnings,
+                }))
+            }
+            _ => Ok(GIFImage::Animation(animation)),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::formats::gif::gif::GIFDecode;
+    use crate::{
+        colors::rgb::RGB,
+        error::ImageError,
+        formats::gif::{
+            blocks::{ColorTable, CommentExtension},
+            encoding::{GIFEncoder, encode_image},
+            errors::{DecodeWarning, GIFParseError},
+            gif::{DisposalMethod, GIFDecode, GIFFrame, GIFImage, LoopCount},
+            limits::{DecoderLimits, Limit},
+        },
+        generic_image::{GenericImage, GenericImageMut},
+        image_buffer::ImageBuffer,
+    };
 
     use super::GIFDecoder;
 
@@ -443,19 +709,299 @@ mod test {
     fn decode_gif() {
         let gif_data = include_bytes!("../../../test-assets/simplest.gif");
 
-        let decoder = GIFDecoder::new(&gif_data[..]);
-        let res = decoder.decode();
-        if let Err(err) = res {
-            panic!("Decode err {:?}", err)
-        }
+        let res = GIFDecoder::new(&gif_data[..]).decode();
+        let image = match res {
+            Ok(GIFImage::Single(gif)) => gif.image,
+            Ok(_) => panic!("expected a single image"),
+            Err(err) => panic!("Decode err {:?}", err),
+        };
+
+        assert_eq!(image.dimensions(), (2, 2));
+    }
+
+    #[test]
+    fn decode_animated_gif() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+
+        let res = GIFDecoder::new(&gif_data[..]).decode();
+        let animation = match res {
+            Ok(GIFImage::Animation(animation)) => animation,
+            Ok(_) => panic!("expected an animation"),
+            Err(err) => panic!("Decode err {:?}", err),
+        };
+
+        assert_eq!(animation.logical_dim, (4, 4));
+        assert_eq!(animation.background_color_index, 3);
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.loop_count, Some(LoopCount::Infinite));
+        assert!(animation.application_extensions.is_empty());
+
+        let frames = &animation.frames;
+        assert_eq!(frames[0].delay_time, 10);
+        assert_eq!(frames[0].disposal_method, DisposalMethod::DoNotDispose);
+        assert!(!frames[0].user_input);
+        assert_eq!(frames[0].indices, [1; 16]);
+
+        assert_eq!(frames[1].delay_time, 20);
+        assert_eq!(frames[1].disposal_method, DisposalMethod::RestoreBackground);
+        assert!(frames[1].user_input);
+        assert_eq!((frames[1].left, frames[1].top), (1, 1));
+        assert_eq!((frames[1].width, frames[1].height), (2, 2));
+
+        // the last frame uses its local color table
+        assert_eq!(frames[2].delay_time, 30);
+        assert_eq!(frames[2].disposal_method, DisposalMethod::NoDisposal);
+        assert_eq!((frames[2].left, frames[2].top), (2, 3));
+        let image = frames[2].to_image();
+        assert_eq!(*image.get_pixel(0, 0), RGB([0, 255, 0]));
+        assert_eq!(*image.get_pixel(1, 0), RGB([255, 255, 0]));
+    }
+
+    #[test]
+    fn decode_application_extensions() {
+        let gif_data = include_bytes!("../../../test-assets/application_extensions.gif");
+        let animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+
+        assert_eq!(animation.frames.len(), 2);
+        // ANIMEXTS1.0 is treated like NETSCAPE2.0
+        assert_eq!(animation.loop_count, Some(LoopCount::Repeat(2)));
+
+        // unknown application extensions are kept as they are
+        assert_eq!(animation.application_extensions.len(), 1);
+        let xmp = &animation.application_extensions[0];
+        assert_eq!(xmp.application_identifier(), b"XMP Data");
+        assert_eq!(xmp.application_code(), b"XMP");
+        assert_eq!(xmp.application_data(), b"<x:xmpmeta/>");
+    }
+
+    #[test]
+    fn decode_comments() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        let animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+
+        assert_eq!(animation.comments.len(), 1);
+        assert_eq!(animation.comments[0].text().unwrap(), "animated test asset");
+    }
+
+    #[test]
+    fn single_image_keeps_comments() {
+        let palette = ColorTable::from_colors(&[RGB([0, 0, 0]), RGB([255, 255, 255])]);
+        let frame = GIFFrame::from_image(&ImageBuffer::new(2, 2), &palette).unwrap();
+        let mut encoder = GIFEncoder::new(Vec::new(), (2, 2), Some(palette));
+        encoder
+            .write_comment(&CommentExtension::new("caption"))
+            .unwrap();
+        encoder.write_frame(&frame).unwrap();
+        encoder
+            .write_comment(&CommentExtension::new("source"))
+            .unwrap();
+        let gif_data = encoder.finish().unwrap();
+
+        let Ok(GIFImage::Single(gif)) = GIFDecoder::new(&gif_data[..]).decode() else {
+            panic!("expected a single image");
+        };
+        let comments = gif.comments.iter().map(|c| c.text().unwrap());
+        assert_eq!(comments.collect::<Vec<_>>(), ["caption", "source"]);
+    }
 
-        println!("Decode successful");
-        match res.unwrap() {
-            crate::formats::gif::gif::GIFImage::None => todo!(),
-            crate::formats::gif::gif::GIFImage::Single(image_buffer) => {
-                println!("DEBUG IMG: {:?}", image_buffer);
+    #[test]
+    fn decode_plain_text() {
+        let gif_data = include_bytes!("../../../test-assets/plain_text.gif");
+        let animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+
+        // the plain text is a frame of its own drawn on top of the white image
+        assert_eq!(animation.frames.len(), 2);
+        assert_eq!(animation.frames[1].delay_time, 100);
+
+        let canvases = animation.composite_frames();
+        let black = RGB([0, 0, 0]);
+        let white = RGB([255, 255, 255]);
+        // left column of the H and the dot of the i
+        assert_eq!(*canvases[1].get_pixel(0, 0), black);
+        assert_eq!(*canvases[1].get_pixel(8, 0), black);
+        assert_eq!(*canvases[1].get_pixel(8, 1), white);
+        assert_eq!(*canvases[1].get_pixel(5, 0), white);
+    }
+
+    #[test]
+    fn decode_interlaced_gif() {
+        let interlaced = include_bytes!("../../../test-assets/interlaced.gif");
+        let reference = include_bytes!("../../../test-assets/interlaced_reference.gif");
+
+        let Ok(GIFImage::Single(interlaced)) = GIFDecoder::new(&interlaced[..]).decode() else {
+            panic!("expected a single image");
+        };
+        let Ok(GIFImage::Single(reference)) = GIFDecoder::new(&reference[..]).decode() else {
+            panic!("expected a single image");
+        };
+
+        assert_eq!(interlaced.image.dimensions(), (6, 13));
+        assert_eq!(interlaced.image.into_vec(), reference.image.into_vec());
+    }
+
+    #[test]
+    fn decode_interlaced_frame() {
+        // a 2x3 interlaced frame has no rows in the second pass
+        let gif_data = include_bytes!("../../../test-assets/interlaced_frame.gif");
+
+        let Ok(GIFImage::Animation(animation)) = GIFDecoder::new(&gif_data[..]).decode() else {
+            panic!("expected an animation");
+        };
+
+        assert_eq!(animation.frames[1].indices, [1, 1, 2, 2, 3, 3]);
+    }
+
+    fn decode_limited(limits: DecoderLimits) -> Result<usize, GIFParseError> {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        let animation = GIFDecoder::new(&gif_data[..])
+            .with_limits(limits)
+            .decode_frames()?;
+        Ok(animation.frames.len())
+    }
+
+    #[test]
+    fn decoder_limits() {
+        // the animation has a 4x4 logical screen and frames of 16, 4 and 2 pixels
+        let limits = DecoderLimits {
+            max_width: 4,
+            max_height: 4,
+            max_frames: 3,
+            max_decoded_bytes: 22,
+            max_frame_pixels: 16,
+        };
+        assert_eq!(decode_limited(limits).unwrap(), 3);
+
+        let exceeded = |limits| match decode_limited(limits) {
+            Err(GIFParseError::LimitExceeded(limit)) => Some(limit),
+            _ => None,
+        };
+        let dimensions = DecoderLimits {
+            max_height: 3,
+            ..limits
+        };
+        assert_eq!(exceeded(dimensions), Some(Limit::Dimensions));
+        let frames = DecoderLimits {
+            max_frames: 2,
+            ..limits
+        };
+        assert_eq!(exceeded(frames), Some(Limit::FrameCount));
+        let decoded_bytes = DecoderLimits {
+            max_decoded_bytes: 21,
+            ..limits
+        };
+        assert_eq!(exceeded(decoded_bytes), Some(Limit::DecodedBytes));
+        let frame_pixels = DecoderLimits {
+            max_frame_pixels: 15,
+            ..limits
+        };
+        assert_eq!(exceeded(frame_pixels), Some(Limit::FramePixels));
+    }
+
+    #[test]
+    fn reject_huge_logical_screen() {
+        let mut gif_data = b"GIF89a".to_vec();
+        gif_data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0]);
+
+        let err = GIFDecoder::new(&gif_data[..]).decode().err().unwrap();
+        assert!(matches!(
+            err,
+            GIFParseError::LimitExceeded(Limit::Dimensions)
+        ));
+        assert!(matches!(ImageError::from(err), ImageError::Limits));
+    }
+
+    #[test]
+    fn check_frame_size_before_decoding() {
+        // a 4x4 logical screen with a 65535x65535 image whose data is missing entirely
+        let mut gif_data = b"GIF89a".to_vec();
+        gif_data.extend_from_slice(&[4, 0, 4, 0, 0, 0, 0]);
+        gif_data.extend_from_slice(&[0x2C, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0]);
+
+        let err = GIFDecoder::new(&gif_data[..]).decode().err().unwrap();
+        assert!(matches!(
+            err,
+            GIFParseError::LimitExceeded(Limit::Dimensions)
+        ));
+    }
+
+    #[test]
+    fn lenient_missing_trailer() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        let truncated = &gif_data[..gif_data.len() - 1];
+
+        assert!(GIFDecoder::new(truncated).decode_frames().is_err());
+
+        let animation = GIFDecoder::new(truncated)
+            .with_lenient(true)
+            .decode_frames()
+            .unwrap();
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.warnings, [DecodeWarning::MissingTrailer]);
+    }
+
+    #[test]
+    fn lenient_fails_without_frames() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        // ends in the looping extension before the first frame
+        let truncated = &gif_data[..30];
+
+        let decoder = GIFDecoder::new(truncated).with_lenient(true);
+        assert!(decoder.decode_frames().is_err());
+    }
+
+    #[test]
+    fn lenient_truncated_image() {
+        let colors = [
+            RGB([255, 255, 255]),
+            RGB([255, 0, 0]),
+            RGB([0, 255, 0]),
+            RGB([0, 0, 255]),
+        ];
+        let palette = ColorTable::from_colors(&colors);
+
+        let mut image = ImageBuffer::new(16, 16);
+        for y in 0..16 {
+            for x in 0..16 {
+                image.put_pixel(x, y, colors[1 + ((x * 7 + y * 3) % 3) as usize]);
+            }
+        }
+        let gif_data = encode_image(Vec::new(), &image, &palette).unwrap();
+        // cut off the trailer, the end of the image data and its block terminator
+        let truncated = &gif_data[..gif_data.len() - 12];
+
+        assert!(GIFDecoder::new(truncated).decode_frames().is_err());
+
+        let decoder = GIFDecoder::new(truncated).with_lenient(true);
+        let animation = decoder.decode_frames().unwrap();
+        assert_eq!(animation.frames.len(), 1);
+
+        // a single image keeps the warnings as well
+        let decoder = GIFDecoder::new(truncated).with_lenient(true);
+        let Ok(GIFImage::Single(gif)) = decoder.decode() else {
+            panic!("expected a single image");
+        };
+        assert_eq!(gif.warnings, animation.warnings);
+
+        let warnings = &animation.warnings;
+        assert!(matches!(
+            warnings[0],
+            DecodeWarning::InvalidImageData { frame: 0, .. }
+        ));
+        let DecodeWarning::MissingPixels { frame: 0, count } = warnings[1] else {
+            panic!("expected missing pixels");
+        };
+        assert_eq!(warnings[2], DecodeWarning::MissingTrailer);
+
+        // the decoded pixels are kept, the missing ones are the background color
+        let decoded = animation.frames[0].to_image();
+        let first_missing = 256 - count as u32;
+        for i in 0..256 {
+            let (x, y) = (i % 16, i / 16);
+            if i < first_missing {
+                assert_eq!(decoded.get_pixel(x, y), image.get_pixel(x, y));
+            } else {
+                assert_eq!(*decoded.get_pixel(x, y), colors[0]);
             }
-            crate::formats::gif::gif::GIFImage::Animation(multi_gif) => todo!(),
         }
     }
 }
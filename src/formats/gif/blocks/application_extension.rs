@@ -1,14 +1,18 @@
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 
-use crate::formats::gif::gif::Version;
+use crate::formats::gif::gif::{LoopCount, Version};
 
-use super::{decoding::read_subblock, Block, BlockLabel, LabeledBlock};
+use super::{
+    Block, BlockLabel, BlockSeparator, LabeledBlock, decoding::read_subblocks,
+    encoding::write_subblocks,
+};
 
 #[derive(Debug, Clone)]
 pub struct ApplicationExtension {
     application_identifier: [u8; 8],
     application_code: [u8; 3],
-    application_data: Box<[u8]>,
+    /// The data sub-blocks following the application identifier, kept as they were read
+    sub_blocks: Vec<Box<[u8]>>,
 }
 
 impl Block for ApplicationExtension {
@@ -22,6 +26,28 @@ impl LabeledBlock for ApplicationExtension {
 }
 
 impl ApplicationExtension {
+    pub fn new(
+        application_identifier: [u8; 8],
+        application_code: [u8; 3],
+        sub_blocks: Vec<Box<[u8]>>,
+    ) -> Self {
+        ApplicationExtension {
+            application_identifier,
+            application_code,
+            sub_blocks,
+        }
+    }
+
+    /// Creates a NETSCAPE2.0 looping extension.
+    pub fn looping(loop_count: LoopCount) -> Self {
+        let repetitions = match loop_count {
+            LoopCount::Infinite => 0u16,
+            LoopCount::Repeat(n) => n,
+        };
+        let [low, high] = repetitions.to_le_bytes();
+        ApplicationExtension::new(*b"NETSCAPE", *b"2.0", vec![Box::new([0x01, low, high])])
+    }
+
     pub fn parse<R: Read>(
         mut reader: R,
     ) -> Result<ApplicationExtension, ApplicationExtensionParseError> {
@@ -30,25 +56,79 @@ impl ApplicationExtension {
         reader.read_exact(&mut buf)?;
 
         if buf[0] as usize != ApplicationExtension::BLOCK_SIZE {
-            return Err(ApplicationExtensionParseError::InvalidBlockSize);
+            return Err(ApplicationExtensionParseError::InvalidBlockSize(buf[0]));
         }
 
-        let application_data = read_subblock(reader)?.into_boxed_slice();
+        let sub_blocks = read_subblocks(reader)?;
         Ok(ApplicationExtension {
-            application_identifier: buf[1..10]
+            application_identifier: buf[1..9]
                 .try_into()
                 .expect("cannot transform Range to const array"),
-            application_code: buf[10..]
+            application_code: buf[9..]
                 .try_into()
                 .expect("cannot transform Range to const array"),
-            application_data,
+            sub_blocks,
         })
     }
+
+    /// Writes the extension introducer, label, the application identifier block and the data
+    /// sub-blocks with their original boundaries.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[
+            BlockSeparator::Extension as u8,
+            Self::LABEL as u8,
+            Self::BLOCK_SIZE as u8,
+        ])?;
+        writer.write_all(&self.application_identifier)?;
+        writer.write_all(&self.application_code)?;
+        write_subblocks(writer, &self.sub_blocks)
+    }
+
+    pub fn application_identifier(&self) -> &[u8; 8] {
+        &self.application_identifier
+    }
+
+    pub fn application_code(&self) -> &[u8; 3] {
+        &self.application_code
+    }
+
+    /// The data sub-blocks following the application identifier.
+    pub fn sub_blocks(&self) -> &[Box<[u8]>] {
+        &self.sub_blocks
+    }
+
+    /// The data of all sub-blocks following the application identifier concatenated.
+    pub fn application_data(&self) -> Vec<u8> {
+        self.sub_blocks.concat()
+    }
+
+    /// Whether this is a NETSCAPE2.0 or the equivalent ANIMEXTS1.0 looping extension.
+    pub fn is_looping_extension(&self) -> bool {
+        matches!(
+            (&self.application_identifier, &self.application_code),
+            (b"NETSCAPE", b"2.0") | (b"ANIMEXTS", b"1.0")
+        )
+    }
+
+    /// The loop count if this is a looping extension (see `is_looping_extension`).
+    ///
+    /// The first sub-block of the extension has id 1 followed by the number of repetitions as
+    /// u16, where 0 means the animation loops forever.
+    pub fn loop_count(&self) -> Option<LoopCount> {
+        if !self.is_looping_extension() {
+            return None;
+        }
+
+        match self.sub_blocks.first().map(|block| &**block) {
+            Some([0x01, low, high, ..]) => Some(LoopCount::from(u16::from_le_bytes([*low, *high]))),
+            _ => None,
+        }
+    }
 }
 
 pub enum ApplicationExtensionParseError {
     Io(io::Error),
-    InvalidBlockSize,
+    InvalidBlockSize(u8),
 }
 
 impl From<io::Error> for ApplicationExtensionParseError {
@@ -56,3 +136,47 @@ impl From<io::Error> for ApplicationExtensionParseError {
         return ApplicationExtensionParseError::Io(arguments);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn netscape_loop_count() {
+        let block = b"\x0bNETSCAPE2.0\x03\x01\x03\x00\x00";
+        let extension = ApplicationExtension::parse(&block[..]).unwrap_or_else(|_| panic!());
+        assert_eq!(extension.application_identifier(), b"NETSCAPE");
+        assert_eq!(extension.application_code(), b"2.0");
+        assert_eq!(extension.loop_count(), Some(LoopCount::Repeat(3)));
+    }
+
+    #[test]
+    fn xmp_sub_blocks_survive_round_trip() {
+        // XMP packets are stored as raw text whose bytes are read as sub-block sizes, followed by
+        // a magic trailer that leads any such sub-block chain to the block terminator
+        let xmp = concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF ",
+            "xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">",
+            "<dc:title>e-ink</dc:title></rdf:Description></rdf:RDF></x:xmpmeta>",
+            "<?xpacket end=\"w\"?>"
+        );
+        let mut block = b"\x0bXMP DataXMP".to_vec();
+        block.extend_from_slice(xmp.as_bytes());
+        block.push(0x01);
+        block.extend((0..=0xFF).rev());
+        block.push(0x00);
+
+        let mut reader = &block[..];
+        let extension = ApplicationExtension::parse(&mut reader).unwrap_or_else(|_| panic!());
+        assert!(reader.is_empty());
+        assert_eq!(extension.application_identifier(), b"XMP Data");
+        assert!(extension.sub_blocks().len() > 1);
+
+        let mut data = Vec::new();
+        extension.write(&mut data).unwrap();
+        assert_eq!(data[..2], [0x21, 0xFF]);
+        assert_eq!(data[2..], block);
+    }
+}
@@ -2,12 +2,16 @@ use core::fmt;
 use std::{
     error::Error,
     fmt::Display,
-    io::{self, Read},
+    io::{self, Read, Write},
 };
 
-use crate::{colors::rgb::RGB, pixel::Pixel};
+use crate::{
+    colors::rgb::RGB,
+    pixel::{EuclidianDistance, Pixel},
+};
 
-const MAX_COLOR_TABLE_SIZE: usize = 256;
+/// The maximum number of colors of a color table
+pub const MAX_COLOR_TABLE_SIZE: usize = 256;
 const MAX_COLOR_TABLE_DATA_SIZE: usize = 3 * MAX_COLOR_TABLE_SIZE;
 
 pub trait ColorTableLookup {
@@ -23,10 +27,69 @@ pub struct ColorTable {
 }
 
 impl ColorTable {
-    /// Calculate the of the color table from the size_flag of the image/logical descriptor
+    /// Creates an unsorted table of `colors`. Colors beyond the maximum table size are ignored.
+    pub fn from_colors(colors: &[RGB<u8>]) -> Self {
+        let size = colors.len().min(MAX_COLOR_TABLE_SIZE);
+        let mut table = ColorTable {
+            data: [RGB::default(); MAX_COLOR_TABLE_SIZE],
+            size,
+            sorted: false,
+        };
+        table.data[..size].copy_from_slice(&colors[..size]);
+        table
+    }
+
+    /// Calculate the number of colors in the color table from the size_flag of the image/logical
+    /// descriptor
     #[inline]
     pub fn calculate_size(size_flag: u8) -> usize {
-        3 * (2 << (size_flag as usize + 1))
+        2 << (size_flag as usize)
+    }
+
+    /// The index of the color of the table closest to `color` (by euclidian distance).
+    pub fn nearest_index(&self, color: RGB<u8>) -> u8 {
+        self.data[..self.size.min(MAX_COLOR_TABLE_SIZE)]
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, entry)| entry.dist_euclidian(&color))
+            .map(|(index, _)| index as u8)
+            .unwrap_or(0)
+    }
+
+    /// Maps every index of this table to the index of the nearest color in `palette`.
+    ///
+    /// Indices outside of the table map to the color black is nearest to, as `lookup_fallback`
+    /// shows them as black. Remapping an image with this only compares each color of the table
+    /// once instead of every pixel.
+    pub fn nearest_indices(&self, palette: &ColorTable) -> [u8; MAX_COLOR_TABLE_SIZE] {
+        let mut mapping = [0u8; MAX_COLOR_TABLE_SIZE];
+        let fallback = palette.nearest_index(RGB::default());
+        for (index, target) in mapping.iter_mut().enumerate() {
+            *target = match self.lookup(index as u8) {
+                Some(color) => palette.nearest_index(color),
+                None => fallback,
+            };
+        }
+        mapping
+    }
+
+    /// The smallest size flag describing a table with at least `size` colors.
+    pub fn size_flag(&self) -> u8 {
+        let mut size_flag = 0;
+        while size_flag < 7 && Self::calculate_size(size_flag) < self.size {
+            size_flag += 1;
+        }
+        size_flag
+    }
+
+    /// Writes the colors of the table. As the size of a color table must be a power of two, the
+    /// table is padded with black up to the size described by `size_flag`.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for index in 0..Self::calculate_size(self.size_flag()) {
+            let color = self.lookup_fallback(index as u8);
+            writer.write_all(&[color[0], color[1], color[2]])?;
+        }
+        Ok(())
     }
 
     pub fn try_from_reader<R: Read>(
@@ -34,36 +97,30 @@ impl ColorTable {
         size_flag: u8,
         sorted: bool,
     ) -> Result<ColorTable, ColorTableParseError> {
+        // the size flag is a 3 bit field, anything larger cannot be described by the format
+        if size_flag > 7 {
+            return Err(ColorTableParseError::TooLarge);
+        }
+
         let mut table = ColorTable {
             size: Self::calculate_size(size_flag),
             sorted,
             data: [RGB::default(); MAX_COLOR_TABLE_SIZE],
         };
 
-        if table.size > MAX_COLOR_TABLE_SIZE {
-            return Err(ColorTableParseError::TooLarge);
-        }
-
         let mut color_table_data = [0u8; MAX_COLOR_TABLE_DATA_SIZE];
+        let data_size = 3 * table.size;
 
-        let mut limited_reader = reader.take(table.size as u64);
-        match limited_reader.read(&mut color_table_data) {
-            Ok(0) => Ok(table), // this means read to end
-            Ok(n) if n == table.size => {
+        match reader.read_exact(&mut color_table_data[..data_size]) {
+            Ok(()) => {
                 // Fill it with RGB values from the data
-                for i in (0..n).step_by(3) {
-                    let index = i / 3;
-                    table.data[index] = RGB::from([
-                        color_table_data[i],
-                        color_table_data[i + 1],
-                        color_table_data[i + 2],
-                    ]);
+                for (index, color) in color_table_data[..data_size].chunks_exact(3).enumerate() {
+                    table.data[index] = RGB::from([color[0], color[1], color[2]]);
                 }
                 Ok(table)
             }
-            Ok(n) if n < table.size => Err(ColorTableParseError::NotEnoughData),
-            Ok(_) => {
-                panic!("color table reader read more than possible")
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(ColorTableParseError::NotEnoughData)
             }
             Err(err) => Err(ColorTableParseError::Io(err)),
         }
@@ -88,6 +145,17 @@ impl ColorTableLookup for ColorTable {
     }
 }
 
+/// Two tables are equal if they contain the same colors, regardless of the (unused) data after
+/// `size`.
+impl PartialEq for ColorTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self.sorted == other.sorted
+            && self.data[..self.size.min(MAX_COLOR_TABLE_SIZE)]
+                == other.data[..other.size.min(MAX_COLOR_TABLE_SIZE)]
+    }
+}
+
 impl Default for ColorTable {
     fn default() -> Self {
         Self {
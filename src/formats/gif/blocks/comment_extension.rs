@@ -1,14 +1,21 @@
 use std::{
-    io::{self, Read},
-    string::FromUtf8Error,
+    borrow::Cow,
+    io::{self, Read, Write},
+    str::Utf8Error,
 };
 
 use crate::formats::gif::gif::Version;
 
-use super::{Block, BlockLabel, LabeledBlock, decoding::read_subblock};
+use super::{
+    Block, BlockLabel, BlockSeparator, LabeledBlock, decoding::read_subblock,
+    encoding::write_subblock,
+};
 
+/// A comment of the data stream. The spec asks for 7-bit ASCII but in the wild any encoding can
+/// be found, so the raw bytes are kept and only interpreted as text on request.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CommentExtension {
-    comment_data: String,
+    comment_data: Vec<u8>,
 }
 
 impl Block for CommentExtension {
@@ -22,17 +29,44 @@ impl LabeledBlock for CommentExtension {
 }
 
 impl CommentExtension {
+    pub fn new(text: &str) -> Self {
+        CommentExtension {
+            comment_data: text.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn from_bytes(comment_data: Vec<u8>) -> Self {
+        CommentExtension { comment_data }
+    }
+
     pub fn parse<R: Read>(reader: &mut R) -> Result<CommentExtension, CommentExtensionParseError> {
-        let comment_data_bytes = read_subblock(reader)?;
-        Ok(CommentExtension {
-            comment_data: String::from_utf8(comment_data_bytes)?,
-        })
+        let comment_data = read_subblock(reader)?;
+        Ok(CommentExtension { comment_data })
+    }
+
+    /// Writes the extension introducer, label and the comment as data sub-blocks.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[BlockSeparator::Extension as u8, Self::LABEL as u8])?;
+        write_subblock(writer, &self.comment_data)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.comment_data
+    }
+
+    /// The comment as text. Fails if the comment is not valid UTF-8 (and thus not ASCII).
+    pub fn text(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(&self.comment_data)
+    }
+
+    /// The comment as text with invalid UTF-8 sequences replaced by `U+FFFD`.
+    pub fn text_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.comment_data)
     }
 }
 
 pub enum CommentExtensionParseError {
     Io(io::Error),
-    InvalidASCII,
 }
 
 impl From<io::Error> for CommentExtensionParseError {
@@ -41,8 +75,31 @@ impl From<io::Error> for CommentExtensionParseError {
     }
 }
 
-impl From<FromUtf8Error> for CommentExtensionParseError {
-    fn from(_arguments: FromUtf8Error) -> Self {
-        CommentExtensionParseError::InvalidASCII
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_parse_comment() {
+        let comment = CommentExtension::new(&"caption ".repeat(40));
+
+        let mut data = Vec::new();
+        comment.write(&mut data).unwrap();
+        assert_eq!(data[..3], [0x21, 0xFE, 255]);
+        assert_eq!(data.len(), 2 + 1 + 255 + 1 + 65 + 1);
+
+        let parsed = CommentExtension::parse(&mut &data[2..]).unwrap_or_else(|_| panic!());
+        assert_eq!(parsed, comment);
+    }
+
+    #[test]
+    fn non_utf8_comment() {
+        // "café" in latin-1
+        let comment =
+            CommentExtension::parse(&mut &b"\x04caf\xe9\x00"[..]).unwrap_or_else(|_| panic!());
+
+        assert!(comment.text().is_err());
+        assert_eq!(comment.text_lossy(), "caf\u{FFFD}");
+        assert_eq!(comment.data(), b"caf\xe9");
     }
 }
@@ -1,5 +1,9 @@
 use std::io::{self, Read};
 
+use crate::formats::gif::lzw::{IncrementalLZWDecoder, LZWDecodeError, LZWProgress, LZWStatus};
+
+use super::encoding::MAX_SUBBLOCK_SIZE;
+
 pub enum FixedSizeBlockParseError {
     InvalidSize { expected: usize, found: usize },
 }
@@ -12,11 +16,8 @@ where
         return Ok([0u8; n]);
     }
     let mut buf = [0u8; n];
-    let read_byte_count = reader.read(&mut buf)?;
-    if read_byte_count != n {
-        // We have not read enough bytes (maybe even n >= !?)
-        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
-    }
+    // a single read may return less bytes than available (e.g. at the end of a BufReader buffer)
+    reader.read_exact(&mut buf)?;
     Ok(buf)
 }
 
@@ -35,15 +36,36 @@ pub fn read_subblock<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
             break;
         }
 
-        //PERF: Optimize collection
-        let mut subblock_buf = vec![0u8; size as usize];
-        reader.read_exact(&mut subblock_buf)?;
-        buf.extend(subblock_buf.iter())
+        let read = (&mut reader).take(size as u64).read_to_end(&mut buf)?;
+        if read < size as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
     }
 
     Ok(buf)
 }
 
+/// Reads the data sub-blocks up to the block terminator, keeping every sub-block as it is. Some
+/// extensions (e.g. XMP data) depend on the sub-block boundaries to be preserved.
+pub fn read_subblocks<R: Read>(mut reader: R) -> io::Result<Vec<Box<[u8]>>> {
+    let mut blocks = Vec::new();
+    loop {
+        let mut size_buf = [0u8; 1];
+        reader.read_exact(&mut size_buf)?;
+
+        let size = size_buf[0];
+        if size == 0 {
+            break;
+        }
+
+        let mut block = vec![0u8; size as usize].into_boxed_slice();
+        reader.read_exact(&mut block)?;
+        blocks.push(block);
+    }
+
+    Ok(blocks)
+}
+
 pub fn skip_subblock<R: Read>(mut reader: R) -> io::Result<()> {
     loop {
         //expect 1st byte to be the size of the data subblock
@@ -57,13 +79,190 @@ pub fn skip_subblock<R: Read>(mut reader: R) -> io::Result<()> {
             break;
         }
 
-        let mut subblock_buf = vec![0u8; size as usize];
-        reader.read_exact(&mut subblock_buf)?;
+        let mut subblock_buf = [0u8; MAX_SUBBLOCK_SIZE];
+        reader.read_exact(&mut subblock_buf[..size as usize])?;
     }
 
     Ok(())
 }
 
-pub fn read_lzw_subblock<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
-    todo!()
+/// Decodes lzw compressed image data straight from its sub-blocks into buffers of the caller.
+///
+/// Only a single sub-block is buffered at a time, so neither the compressed nor the decoded data
+/// has to be held as a whole, e.g. to decode an image row by row.
+pub struct SubblockLZWDecoder<R: Read> {
+    reader: R,
+    lzw: IncrementalLZWDecoder,
+    block: [u8; MAX_SUBBLOCK_SIZE],
+    /// the range of `block` not passed to the lzw decoder yet
+    block_range: (usize, usize),
+    /// the block terminator was read
+    terminated: bool,
+    /// the data ended in the middle of the current sub-block
+    truncated: bool,
+}
+
+impl<R: Read> SubblockLZWDecoder<R> {
+    /// Creates a decoder reading sub-blocks from `reader`, which must be positioned right after
+    /// the lzw minimum code size.
+    pub fn new(reader: R, minimum_code_size: u8) -> Result<Self, LZWDecodeError> {
+        Ok(SubblockLZWDecoder {
+            reader,
+            lzw: IncrementalLZWDecoder::new(minimum_code_size)?,
+            block: [0; MAX_SUBBLOCK_SIZE],
+            block_range: (0, 0),
+            terminated: false,
+            truncated: false,
+        })
+    }
+
+    /// Decodes until `output` is full or the image data ends. `consumed` of the result counts the
+    /// compressed bytes used, not including the sub-block sizes.
+    ///
+    /// Image data ending before `output` is full without an end of information code (or ending
+    /// in the middle of a sub-block) is an error.
+    pub fn read_into(&mut self, output: &mut [u8]) -> LZWProgress {
+        let mut progress = LZWProgress {
+            consumed: 0,
+            written: 0,
+            status: Ok(LZWStatus::Ok),
+        };
+
+        loop {
+            // words decoded earlier are written before any further input is read
+            let (start, end) = self.block_range;
+            let step = self
+                .lzw
+                .decode(&self.block[start..end], &mut output[progress.written..]);
+            self.block_range.0 += step.consumed;
+            progress.consumed += step.consumed;
+            progress.written += step.written;
+
+            match step.status {
+                Ok(LZWStatus::Ok) => {}
+                status => {
+                    progress.status = status;
+                    return progress;
+                }
+            }
+            if progress.written == output.len() {
+                return progress;
+            }
+
+            // the sub-block is used up and more input is needed
+            if self.truncated {
+                progress.status = Err(LZWDecodeError::Io(io::Error::from(
+                    io::ErrorKind::UnexpectedEof,
+                )));
+                return progress;
+            }
+            if self.terminated {
+                progress.status = Err(LZWDecodeError::UnexpectedEOF);
+                return progress;
+            }
+            if let Err(err) = self.read_block() {
+                progress.status = Err(LZWDecodeError::Io(err));
+                return progress;
+            }
+        }
+    }
+
+    /// Reads the next sub-block into `block`. A sub-block cut off by the end of the data is kept
+    /// as far as it was read.
+    fn read_block(&mut self) -> io::Result<()> {
+        let mut size_buf = [0u8; 1];
+        self.reader.read_exact(&mut size_buf)?;
+        let size = size_buf[0] as usize;
+        if size == 0 {
+            self.terminated = true;
+            self.block_range = (0, 0);
+            return Ok(());
+        }
+
+        let mut filled = 0;
+        while filled < size {
+            match self.reader.read(&mut self.block[filled..size]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.truncated = filled < size;
+        self.block_range = (0, filled);
+        Ok(())
+    }
+
+    /// Skips the rest of the image data up to and including the block terminator and returns
+    /// the reader positioned after it.
+    pub fn finish(mut self) -> io::Result<R> {
+        if !self.terminated {
+            if self.truncated {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            skip_subblock(&mut self.reader)?;
+        }
+        Ok(self.reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::gif::{blocks::encoding::write_subblock, lzw::LZWEncoder};
+
+    use super::*;
+
+    #[test]
+    fn decode_subblocks_row_by_row() {
+        let (width, height) = (40, 30);
+        let indices: Vec<u8> = (0..width * height).map(|i| ((i * 7) % 13) as u8).collect();
+        let mut data = Vec::new();
+        write_subblock(&mut data, &LZWEncoder::encode(&indices, 4).unwrap()).unwrap();
+        // the block following the image data
+        data.push(0x3B);
+
+        let mut reader = &data[..];
+        let mut decoder = SubblockLZWDecoder::new(&mut reader, 4).unwrap();
+        let mut row = vec![0u8; width];
+        for y in 0..height {
+            let progress = decoder.read_into(&mut row);
+            assert!(progress.status.is_ok());
+            assert_eq!(progress.written, width);
+            assert_eq!(row, indices[y * width..(y + 1) * width]);
+        }
+
+        let progress = decoder.read_into(&mut row);
+        assert!(matches!(progress.status, Ok(LZWStatus::Done)));
+        assert_eq!(progress.written, 0);
+
+        let reader = decoder.finish().unwrap();
+        assert_eq!(*reader, [0x3B]);
+    }
+
+    #[test]
+    fn decode_truncated_subblocks() {
+        let indices = vec![1u8; 1000];
+        let mut data = Vec::new();
+        write_subblock(&mut data, &LZWEncoder::encode(&indices, 2).unwrap()).unwrap();
+        data.truncate(data.len() - 3);
+
+        let mut decoder = SubblockLZWDecoder::new(&data[..], 2).unwrap();
+        let mut output = vec![0u8; 1000];
+        let progress = decoder.read_into(&mut output);
+        assert!(matches!(progress.status, Err(LZWDecodeError::Io(_))));
+        assert!(progress.written < 1000);
+        assert!(output[..progress.written].iter().all(|i| *i == 1));
+
+        // words that did not fit into the output are still written after the data ended
+        let mut decoder = SubblockLZWDecoder::new(&data[..], 2).unwrap();
+        let mut written = 0;
+        loop {
+            let chunk = decoder.read_into(&mut output[written..(written + 7).min(1000)]);
+            written += chunk.written;
+            if chunk.status.is_err() {
+                break;
+            }
+        }
+        assert_eq!(written, progress.written);
+    }
 }
@@ -0,0 +1,27 @@
+use std::io::{self, Write};
+
+/// The maximum size of a single data sub-block
+pub const MAX_SUBBLOCK_SIZE: usize = 255;
+
+/// Writes `data` as a chain of data sub-blocks followed by the block terminator.
+pub fn write_subblock<W: Write>(mut writer: W, data: &[u8]) -> io::Result<()> {
+    for chunk in data.chunks(MAX_SUBBLOCK_SIZE) {
+        writer.write_all(&[chunk.len() as u8])?;
+        writer.write_all(chunk)?;
+    }
+    //block terminator
+    writer.write_all(&[0u8])
+}
+
+/// Writes `blocks` as data sub-blocks keeping their boundaries, followed by the block terminator.
+/// Empty blocks are left out and blocks larger than a sub-block are split.
+pub fn write_subblocks<W: Write>(mut writer: W, blocks: &[Box<[u8]>]) -> io::Result<()> {
+    for block in blocks {
+        for chunk in block.chunks(MAX_SUBBLOCK_SIZE) {
+            writer.write_all(&[chunk.len() as u8])?;
+            writer.write_all(chunk)?;
+        }
+    }
+    //block terminator
+    writer.write_all(&[0u8])
+}
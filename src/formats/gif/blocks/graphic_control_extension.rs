@@ -1,8 +1,8 @@
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 
 use crate::formats::gif::gif::{DisposalMethod, Version};
 
-use super::{Block, BlockLabel, LabeledBlock};
+use super::{Block, BlockLabel, BlockSeparator, LabeledBlock};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -42,6 +42,23 @@ impl Default for GraphicControlExtension {
 }
 
 impl GraphicControlExtension {
+    pub fn new(
+        delay_time: u16,
+        disposal_method: DisposalMethod,
+        user_input: bool,
+        transparent_color_index: Option<u8>,
+    ) -> Self {
+        GraphicControlExtension {
+            flags: ((disposal_method as u8) << Self::DISPOSAL_METHOD_OFFSET)
+                | ((user_input as u8) << Self::USER_INPUT_FLAG_OFFSET)
+                | ((transparent_color_index.is_some() as u8)
+                    << Self::TRANSPARENT_COLOR_FLAG_OFFSET),
+            delay_time,
+            transparent_color_index: transparent_color_index
+                .unwrap_or(DEFAULT_TRANSPARENT_COLOR_INDEX),
+        }
+    }
+
     pub fn parse<R: Read>(
         reader: &mut R,
     ) -> Result<GraphicControlExtension, GraphicControlExtensionParseError> {
@@ -64,6 +81,18 @@ impl GraphicControlExtension {
         })
     }
 
+    /// Writes the extension introducer, label, the block and the block terminator.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[
+            BlockSeparator::Extension as u8,
+            Self::LABEL as u8,
+            Self::BLOCK_SIZE as u8,
+            self.flags,
+        ])?;
+        writer.write_all(&self.delay_time.to_le_bytes())?;
+        writer.write_all(&[self.transparent_color_index, 0])
+    }
+
     //Flags
     //  RESERVED
     const DISPOSAL_METHOD: u8 = 0b11100;
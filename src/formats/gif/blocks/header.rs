@@ -1,7 +1,7 @@
 use std::{
     error::Error,
     fmt::Display,
-    io::{self, Read},
+    io::{self, Read, Write},
 };
 
 use crate::formats::gif::{
@@ -56,6 +56,11 @@ impl Header {
 
         Header::try_from(&buf)
     }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(GIF_CONST_SIGNATURE)?;
+        writer.write_all(self.version.as_bytes())
+    }
 }
 
 impl TryFrom<&[u8; Self::BLOCK_SIZE]> for Header {
@@ -1,11 +1,11 @@
 use std::{
-    io::{self, Read},
+    io::{self, Read, Write},
     mem,
 };
 
 use crate::formats::gif::gif::Version;
 
-use super::Block;
+use super::{Block, ColorTable};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -33,7 +33,7 @@ impl LogicalScreenDescriptor {
     pub const COLOR_RESOLUTION_OFFSET: u8 = 4;
     pub const SORT_FLAG_BIT: u8 = 0b1000;
     pub const SORT_FLAG_OFFSET: u8 = 3;
-    pub const GLOBAL_COLOR_TABLE_SIZE_BITS: u8 = 0b0;
+    pub const GLOBAL_COLOR_TABLE_SIZE_BITS: u8 = 0b111;
     pub const GLOBAL_COLOR_TABLE_SIZE_OFFSET: u8 = 0;
 
     pub fn global_color_table_flag(&self) -> bool {
@@ -58,6 +58,31 @@ impl Block for LogicalScreenDescriptor {
 }
 
 impl LogicalScreenDescriptor {
+    /// Creates a descriptor for a logical screen of `width` x `height`, announcing the
+    /// `global_color_table` if there is one.
+    pub fn new(
+        width: u16,
+        height: u16,
+        global_color_table: Option<&ColorTable>,
+        background_color_index: u8,
+    ) -> Self {
+        // we always claim the full 8 bit color resolution
+        let mut flags = 0b111 << Self::COLOR_RESOLUTION_OFFSET;
+        if let Some(table) = global_color_table {
+            flags |= Self::GLOBAL_COLOR_TABLE_FLAG_BIT
+                | ((table.sorted as u8) << Self::SORT_FLAG_OFFSET)
+                | (table.size_flag() << Self::GLOBAL_COLOR_TABLE_SIZE_OFFSET);
+        }
+
+        LogicalScreenDescriptor {
+            logical_screen_width: width,
+            logical_screen_height: height,
+            flags,
+            background_color_index,
+            pixel_aspect_ratio: 0,
+        }
+    }
+
     pub fn parse<R: Read>(
         reader: &mut R,
     ) -> Result<LogicalScreenDescriptor, LogicalScreenDescriptorParseError> {
@@ -67,6 +92,16 @@ impl LogicalScreenDescriptor {
         let descriptor = LogicalScreenDescriptor::from(&buf);
         Ok(descriptor)
     }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.logical_screen_width.to_le_bytes())?;
+        writer.write_all(&self.logical_screen_height.to_le_bytes())?;
+        writer.write_all(&[
+            self.flags,
+            self.background_color_index,
+            self.pixel_aspect_ratio,
+        ])
+    }
 }
 
 // Conversions
@@ -2,7 +2,8 @@ mod application_extension;
 mod categories;
 mod color_table;
 mod comment_extension;
-mod decoding;
+pub mod decoding;
+pub mod encoding;
 mod graphic_control_extension;
 mod header;
 mod logical_screen_descriptor;
@@ -72,6 +72,33 @@ impl PlainTextExtension {
         })
     }
 
+    /// Position of the text grid on the logical screen as (left, top)
+    pub fn text_grid_position(&self) -> (u16, u16) {
+        (self.text_grid_left_position, self.text_grid_top_position)
+    }
+
+    /// Size of the text grid in pixels as (width, height)
+    pub fn text_grid_dim(&self) -> (u16, u16) {
+        (self.text_grid_width, self.text_grid_height)
+    }
+
+    /// Size of a single character cell in pixels as (width, height)
+    pub fn char_cell_dim(&self) -> (u8, u8) {
+        (self.char_cell_width, self.char_cell_height)
+    }
+
+    pub fn text_foreground_color_index(&self) -> u8 {
+        self.text_foreground_color_index
+    }
+
+    pub fn text_background_color_index(&self) -> u8 {
+        self.text_background_color_index
+    }
+
+    pub fn text(&self) -> &str {
+        &self.plain_text_data
+    }
+
     pub fn parse_strict<R: Read>(
         reader: &mut R,
     ) -> Result<PlainTextExtension, PlainTextExtensionParseError> {
@@ -1,15 +1,15 @@
 //! yes I know it is technically multiple blocks, however they must follow each other and only the
 //! ImageDescriptor has a Label, as such it is entierly plausible to let them act as one.
 
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 
 use crate::formats::gif::{
-    blocks::decoding::{read_n_byte, read_subblock},
+    blocks::decoding::{SubblockLZWDecoder, read_n_byte, skip_subblock},
     gif::Version,
-    lzw::{LZW, LZWDecodeError, LZWDecoder},
+    lzw::LZWDecodeError,
 };
 
-use super::{Block, BlockLabel, ColorTable, ColorTableParseError, LabeledBlock};
+use super::{Block, BlockLabel, BlockSeparator, ColorTable, ColorTableParseError, LabeledBlock};
 
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
@@ -28,6 +28,39 @@ pub struct ImageDescriptor {
 }
 
 impl ImageDescriptor {
+    /// Creates a descriptor for a (not interlaced) image at `position` announcing the
+    /// `local_color_table` if there is one.
+    pub fn new(
+        position: ImagePosition,
+        (width, height): (u16, u16),
+        local_color_table: Option<&ColorTable>,
+    ) -> Self {
+        let mut flags = 0;
+        if let Some(table) = local_color_table {
+            flags |= Self::LOCAL_COLOR_TABLE_FLAG
+                | ((table.sorted as u8) << Self::SORT_FLAG_OFFSET)
+                | (table.size_flag() << Self::LOCAL_COLOR_TABLE_SIZE_OFFSET);
+        }
+
+        ImageDescriptor {
+            image_left_position: position.left,
+            image_top_position: position.top,
+            image_width: width,
+            image_height: height,
+            flags,
+        }
+    }
+
+    /// Writes the image separator and the descriptor.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[BlockSeparator::Image as u8])?;
+        writer.write_all(&self.image_left_position.to_le_bytes())?;
+        writer.write_all(&self.image_top_position.to_le_bytes())?;
+        writer.write_all(&self.image_width.to_le_bytes())?;
+        writer.write_all(&self.image_height.to_le_bytes())?;
+        writer.write_all(&[self.flags])
+    }
+
     const LOCAL_COLOR_TABLE_FLAG: u8 = 0b1000_0000;
     const LOCAL_COLOR_TABLE_FLAG_OFFSET: u8 = 7;
     const INTERLACE_FLAG: u8 = 0b0100_0000;
@@ -107,7 +140,23 @@ impl TableBasedImage {
         return &self.image_data;
     }
 
+    pub fn into_data(self) -> Vec<u8> {
+        self.image_data
+    }
+
     pub fn parse<R: Read>(reader: &mut R) -> Result<TableBasedImage, TableBasedImageParseError> {
+        let (descriptor, color_table) = Self::parse_descriptor(reader)?;
+        match Self::parse_data(reader, descriptor, color_table)? {
+            (_, Some(err)) => Err(err),
+            (image, None) => Ok(image),
+        }
+    }
+
+    /// Parses the image descriptor and the local color table, i.e. everything before the image
+    /// data. This allows to check the size of the image before its data is decoded.
+    pub fn parse_descriptor<R: Read>(
+        reader: &mut R,
+    ) -> Result<(ImageDescriptor, Option<ColorTable>), TableBasedImageParseError> {
         //there is no size_flag
         const READ_SIZE: usize = TableBasedImage::BLOCK_SIZE;
         let buf: [u8; READ_SIZE] = read_n_byte(reader)?;
@@ -124,18 +173,52 @@ impl TableBasedImage {
             )?);
         }
 
+        Ok((descriptor, color_table_opt))
+    }
+
+    /// Decodes the image data following `parse_descriptor` into exactly as many color indices as
+    /// the image has pixels.
+    ///
+    /// If the image data is truncated or contains invalid lzw codes the image is returned with
+    /// the data decoded up to that point, together with the error that stopped decoding.
+    pub fn parse_data<R: Read>(
+        reader: &mut R,
+        descriptor: ImageDescriptor,
+        color_table: Option<ColorTable>,
+    ) -> Result<(TableBasedImage, Option<TableBasedImageParseError>), TableBasedImageParseError>
+    {
         //read lzw image
         let buf: [u8; 1] = read_n_byte(reader)?;
         let minimum_code_size = buf[0];
 
-        let data = read_subblock(reader)?;
-        let image_data = LZWDecoder::decode(&data[..], minimum_code_size)?;
+        let (width, height) = descriptor.image_dim();
+        let pixel_count = width as usize * height as usize;
 
-        Ok(TableBasedImage {
+        let mut image = TableBasedImage {
             descriptor,
-            color_table: color_table_opt,
-            image_data,
-        })
+            color_table,
+            image_data: Vec::new(),
+        };
+
+        let mut decoder = match SubblockLZWDecoder::new(&mut *reader, minimum_code_size) {
+            Ok(decoder) => decoder,
+            Err(err) => {
+                skip_subblock(reader)?;
+                return Ok((image, Some(err.into())));
+            }
+        };
+
+        // the data is decoded straight into the image, anything beyond the image is skipped
+        image.image_data = vec![0u8; pixel_count];
+        let progress = decoder.read_into(&mut image.image_data);
+        image.image_data.truncate(progress.written);
+
+        let error = match (progress.status, decoder.finish()) {
+            (Err(err), _) => Some(err.into()),
+            (Ok(_), Err(err)) => Some(TableBasedImageParseError::Io(err)),
+            (Ok(_), Ok(_)) => None,
+        };
+        Ok((image, error))
     }
 }
 
@@ -159,6 +242,9 @@ impl From<ColorTableParseError> for TableBasedImageParseError {
 
 impl From<LZWDecodeError> for TableBasedImageParseError {
     fn from(value: LZWDecodeError) -> Self {
-        TableBasedImageParseError::InvalidLZWCode
+        match value {
+            LZWDecodeError::Io(err) => TableBasedImageParseError::Io(err),
+            _ => TableBasedImageParseError::InvalidLZWCode,
+        }
     }
 }
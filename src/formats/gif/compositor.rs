@@ -0,0 +1,308 @@
+//! Turns the frames of a gif (which only cover a sub rectangle of the logical screen) into full
+//! logical screen canvases by applying the disposal method of each frame before the next one is
+//! drawn.
+
+use crate::{
+    colors::{rgb::RGB, rgba::RGBA},
+    generic_image::{GenericImage, GenericImageMut},
+    image_buffer::ImageBuffer,
+    pixel::Pixel,
+};
+
+use super::{
+    blocks::ColorTableLookup,
+    gif::{DisposalMethod, GIFFrame, MultiGIF},
+};
+
+/// The area of the logical screen covered by a frame, already clipped to the logical screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct FrameRect {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+impl FrameRect {
+    fn clipped(frame: &GIFFrame, (width, height): (u32, u32)) -> Self {
+        let left = (frame.left as u32).min(width);
+        let top = (frame.top as u32).min(height);
+        FrameRect {
+            left,
+            top,
+            right: (frame.left as u32 + frame.width as u32).min(width),
+            bottom: (frame.top as u32 + frame.height as u32).min(height),
+        }
+    }
+}
+
+/// A pixel type gif frames can be composited onto.
+pub trait CanvasPixel: Pixel<Subpixel = u8> {
+    /// Converts a color of a color table into the pixel type.
+    fn from_color(color: RGB<u8>) -> Self;
+    /// The pixel used for the background, e.g. the initial canvas and restore to background.
+    fn background(background_color: RGB<u8>) -> Self;
+}
+
+impl CanvasPixel for RGB<u8> {
+    fn from_color(color: RGB<u8>) -> Self {
+        color
+    }
+    fn background(background_color: RGB<u8>) -> Self {
+        background_color
+    }
+}
+
+/// Composited with an alpha channel the background is fully transparent, as most viewers do it,
+/// so the result can be drawn on top of other content.
+impl CanvasPixel for RGBA<u8> {
+    fn from_color(color: RGB<u8>) -> Self {
+        RGBA([color[0], color[1], color[2], u8::MAX])
+    }
+    fn background(_background_color: RGB<u8>) -> Self {
+        RGBA([0, 0, 0, 0])
+    }
+}
+
+/// Composites gif frames one after another onto a canvas of the logical screen size.
+///
+/// The disposal method of a frame is applied lazily, i.e. right before the next frame is drawn, so
+/// that the canvas returned by `composite` is always the image that should be shown for the frame.
+/// Transparent pixels of a frame leave the canvas untouched.
+#[derive(Debug, Clone)]
+pub struct Compositor<P: CanvasPixel = RGB<u8>> {
+    canvas: ImageBuffer<P, Vec<u8>>,
+    background: P,
+    /// The disposal of the last drawn frame and the area it covered
+    pending_disposal: Option<(DisposalMethod, FrameRect)>,
+    /// The canvas content below the last drawn frame, if it has to be restored to previous
+    previous: Vec<P>,
+}
+
+impl<P: CanvasPixel> Compositor<P> {
+    /// Creates a compositor with a canvas of `logical_dim` filled with the background pixel for
+    /// `background_color`.
+    pub fn new(logical_dim: (u16, u16), background_color: RGB<u8>) -> Self {
+        let background = P::background(background_color);
+        let mut canvas = ImageBuffer::new(logical_dim.0 as u32, logical_dim.1 as u32);
+        for y in 0..logical_dim.1 as u32 {
+            for x in 0..logical_dim.0 as u32 {
+                canvas.put_pixel(x, y, background);
+            }
+        }
+
+        Compositor {
+            canvas,
+            background,
+            pending_disposal: None,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Creates a compositor for the logical screen and background of an animation.
+    pub fn for_animation(animation: &MultiGIF) -> Self {
+        Self::new(animation.logical_dim, animation.background_color)
+    }
+
+    /// The canvas as it was left by the last call to `composite`.
+    pub fn canvas(&self) -> &ImageBuffer<P, Vec<u8>> {
+        &self.canvas
+    }
+
+    pub fn into_canvas(self) -> ImageBuffer<P, Vec<u8>> {
+        self.canvas
+    }
+
+    /// Disposes the previous frame and draws `frame` onto the canvas.
+    ///
+    /// Returns the full canvas as it should be displayed for `frame`.
+    pub fn composite(&mut self, frame: &GIFFrame) -> &ImageBuffer<P, Vec<u8>> {
+        self.dispose();
+
+        let rect = FrameRect::clipped(frame, self.canvas.dimensions());
+
+        if frame.disposal_method == DisposalMethod::RestorePrevious {
+            self.previous.clear();
+            for y in rect.top..rect.bottom {
+                for x in rect.left..rect.right {
+                    self.previous.push(*self.canvas.get_pixel(x, y));
+                }
+            }
+        }
+
+        for y in rect.top..rect.bottom {
+            for x in rect.left..rect.right {
+                let index = (y - frame.top as u32) as usize * frame.width as usize
+                    + (x - frame.left as u32) as usize;
+                match frame.indices.get(index) {
+                    Some(color_index) if Some(*color_index) == frame.transparent_color_index => {}
+                    Some(color_index) => {
+                        let color = frame.color_table.lookup_fallback(*color_index);
+                        self.canvas.put_pixel(x, y, P::from_color(color));
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        self.pending_disposal = Some((frame.disposal_method, rect));
+        &self.canvas
+    }
+
+    /// Applies the disposal method of the last drawn frame, leaving the canvas the next frame is
+    /// drawn onto. `composite` does this by itself, calling it beforehand has no further effect.
+    pub fn dispose(&mut self) {
+        match self.pending_disposal.take() {
+            Some((DisposalMethod::RestoreBackground, rect)) => {
+                for y in rect.top..rect.bottom {
+                    for x in rect.left..rect.right {
+                        self.canvas.put_pixel(x, y, self.background);
+                    }
+                }
+            }
+            Some((DisposalMethod::RestorePrevious, rect)) => {
+                let mut previous = self.previous.iter();
+                for y in rect.top..rect.bottom {
+                    for x in rect.left..rect.right {
+                        if let Some(color) = previous.next() {
+                            self.canvas.put_pixel(x, y, *color);
+                        }
+                    }
+                }
+            }
+            Some((DisposalMethod::NoDisposal | DisposalMethod::DoNotDispose, _)) | None => {}
+        }
+    }
+}
+
+impl MultiGIF {
+    /// Composites all frames and returns one full logical screen canvas per frame.
+    pub fn composite_frames(&self) -> Vec<ImageBuffer<RGB<u8>, Vec<u8>>> {
+        let mut compositor = Compositor::for_animation(self);
+        self.frames
+            .iter()
+            .map(|frame| compositor.composite(frame).clone())
+            .collect()
+    }
+
+    /// Composites all frames with an alpha channel. Transparent pixels and the background stay
+    /// transparent, e.g. to overlay the animation on top of other content.
+    pub fn composite_frames_with_alpha(&self) -> Vec<ImageBuffer<RGBA<u8>, Vec<u8>>> {
+        let mut compositor = Compositor::for_animation(self);
+        self.frames
+            .iter()
+            .map(|frame| compositor.composite(frame).clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::gif::{
+        better_decoder::GIFDecoder,
+        blocks::ColorTable,
+        gif::{GIFDecode, GIFImage},
+    };
+
+    use super::*;
+
+    const BLACK: RGB<u8> = RGB([0, 0, 0]);
+    const RED: RGB<u8> = RGB([255, 0, 0]);
+    const BLUE: RGB<u8> = RGB([0, 0, 255]);
+    const WHITE: RGB<u8> = RGB([255, 255, 255]);
+
+    fn frame(
+        (left, top): (u16, u16),
+        (width, height): (u16, u16),
+        color_index: u8,
+        disposal_method: DisposalMethod,
+    ) -> GIFFrame {
+        GIFFrame {
+            left,
+            top,
+            width,
+            height,
+            indices: vec![color_index; width as usize * height as usize],
+            color_table: ColorTable::from_colors(&[BLACK, RED, BLUE, WHITE]),
+            delay_time: 0,
+            disposal_method,
+            user_input: false,
+            transparent_color_index: None,
+        }
+    }
+
+    fn pixels<P: CanvasPixel>(image: &ImageBuffer<P, Vec<u8>>) -> Vec<P> {
+        image.iter().map(|(_, _, pixel)| *pixel).collect()
+    }
+
+    #[test]
+    fn composite_animated_gif() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        let Ok(GIFImage::Animation(animation)) = GIFDecoder::new(&gif_data[..]).decode() else {
+            panic!("expected an animation");
+        };
+
+        let canvases = animation.composite_frames();
+        assert_eq!(canvases.len(), 3);
+
+        assert_eq!(pixels(&canvases[0]), [RED; 16]);
+
+        // do not dispose keeps the red frame, the blue one is drawn on top
+        assert_eq!(*canvases[1].get_pixel(0, 0), RED);
+        assert_eq!(*canvases[1].get_pixel(1, 1), BLUE);
+        assert_eq!(*canvases[1].get_pixel(2, 2), BLUE);
+
+        // the blue frame is restored to the background (white) before the last frame is drawn
+        assert_eq!(*canvases[2].get_pixel(0, 0), RED);
+        assert_eq!(*canvases[2].get_pixel(1, 1), WHITE);
+        assert_eq!(*canvases[2].get_pixel(2, 2), WHITE);
+        assert_eq!(*canvases[2].get_pixel(2, 3), RGB([0, 255, 0]));
+        assert_eq!(*canvases[2].get_pixel(3, 3), RGB([255, 255, 0]));
+    }
+
+    #[test]
+    fn restore_previous() {
+        let mut compositor: Compositor = Compositor::new((2, 2), WHITE);
+
+        compositor.composite(&frame((0, 0), (2, 2), 1, DisposalMethod::DoNotDispose));
+        let canvas =
+            compositor.composite(&frame((1, 0), (1, 2), 2, DisposalMethod::RestorePrevious));
+        assert_eq!(pixels(canvas), [RED, BLUE, RED, BLUE]);
+
+        let canvas = compositor.composite(&frame((0, 1), (1, 1), 0, DisposalMethod::NoDisposal));
+        assert_eq!(pixels(canvas), [RED, RED, BLACK, RED]);
+    }
+
+    #[test]
+    fn restore_background_and_clipping() {
+        let mut compositor: Compositor = Compositor::new((2, 2), WHITE);
+
+        // the frame reaches outside of the logical screen and is clipped
+        let canvas =
+            compositor.composite(&frame((1, 1), (3, 3), 1, DisposalMethod::RestoreBackground));
+        assert_eq!(pixels(canvas), [WHITE, WHITE, WHITE, RED]);
+
+        let canvas = compositor.composite(&frame((0, 0), (1, 1), 2, DisposalMethod::NoDisposal));
+        assert_eq!(pixels(canvas), [BLUE, WHITE, WHITE, WHITE]);
+    }
+
+    #[test]
+    fn transparent_pixels_keep_canvas() {
+        let gif_data = include_bytes!("../../../test-assets/transparent.gif");
+        let animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+        assert_eq!(animation.frames[0].transparent_color_index, Some(0));
+
+        let canvases = animation.composite_frames();
+        // transparent pixels show the background (white) or the previous frame (red)
+        assert_eq!(*canvases[0].get_pixel(0, 0), WHITE);
+        assert_eq!(*canvases[0].get_pixel(1, 1), RED);
+        assert_eq!(*canvases[1].get_pixel(0, 0), RGB([0, 255, 0]));
+        assert_eq!(*canvases[1].get_pixel(1, 1), RED);
+        assert_eq!(*canvases[1].get_pixel(3, 3), WHITE);
+
+        let canvases = animation.composite_frames_with_alpha();
+        assert_eq!(*canvases[1].get_pixel(0, 0), RGBA([0, 255, 0, 255]));
+        assert_eq!(*canvases[1].get_pixel(1, 1), RGBA([255, 0, 0, 255]));
+        assert_eq!(*canvases[1].get_pixel(3, 3), RGBA([0, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,306 @@
+//! Encodes indexed frames into a GIF89a data stream.
+
+use std::io::Write;
+
+use crate::{colors::rgb::RGB, generic_image::GenericImage, image_buffer::ImageBuffer};
+
+use super::{
+    blocks::{
+        ApplicationExtension, BlockSeparator, ColorTable, CommentExtension,
+        GraphicControlExtension, Header, ImageDescriptor, ImagePosition, LogicalScreenDescriptor,
+        encoding::write_subblock,
+    },
+    errors::GIFEncodeError,
+    gif::{DisposalMethod, GIFFrame, Version},
+    lzw::LZWEncoder,
+};
+
+/// Writes frames one after another into a gif data stream.
+///
+/// The header, logical screen descriptor and global color table are written with the first
+/// block, the trailer by `finish`. A frame only gets a local color table if its color table
+/// differs from the global one.
+pub struct GIFEncoder<W: Write> {
+    writer: W,
+    logical_dim: (u16, u16),
+    global_color_table: Option<ColorTable>,
+    background_color_index: u8,
+    header_written: bool,
+}
+
+impl<W: Write> GIFEncoder<W> {
+    pub fn new(writer: W, logical_dim: (u16, u16), global_color_table: Option<ColorTable>) -> Self {
+        GIFEncoder {
+            writer,
+            logical_dim,
+            global_color_table,
+            background_color_index: 0,
+            header_written: false,
+        }
+    }
+
+    /// Sets the index of the background color in the global color table. Must be called before
+    /// the first block is written.
+    pub fn set_background_color_index(&mut self, background_color_index: u8) {
+        self.background_color_index = background_color_index;
+    }
+
+    fn write_header(&mut self) -> Result<(), GIFEncodeError> {
+        if self.header_written {
+            return Ok(());
+        }
+
+        Header {
+            version: Version::Version89a,
+        }
+        .write(&mut self.writer)?;
+        LogicalScreenDescriptor::new(
+            self.logical_dim.0,
+            self.logical_dim.1,
+            self.global_color_table.as_ref(),
+            self.background_color_index,
+        )
+        .write(&mut self.writer)?;
+        if let Some(table) = &self.global_color_table {
+            table.write(&mut self.writer)?;
+        }
+
+        self.header_written = true;
+        Ok(())
+    }
+
+    /// Writes an application extension. Looping extensions must be written before the first
+    /// frame.
+    pub fn write_application_extension(
+        &mut self,
+        extension: &ApplicationExtension,
+    ) -> Result<(), GIFEncodeError> {
+        self.write_header()?;
+        extension.write(&mut self.writer)?;
+        Ok(())
+    }
+
+    pub fn write_comment(&mut self, comment: &CommentExtension) -> Result<(), GIFEncodeError> {
+        self.write_header()?;
+        comment.write(&mut self.writer)?;
+        Ok(())
+    }
+
+    /// Writes the graphic control extension (if the frame needs one) and the frame as a table
+    /// based image.
+    pub fn write_frame(&mut self, frame: &GIFFrame) -> Result<(), GIFEncodeError> {
+        if frame.indices.len() != frame.width as usize * frame.height as usize {
+            return Err(GIFEncodeError::InvalidFrameData);
+        }
+        if frame.left as u32 + frame.width as u32 > self.logical_dim.0 as u32
+            || frame.top as u32 + frame.height as u32 > self.logical_dim.1 as u32
+        {
+            return Err(GIFEncodeError::FrameOutOfBounds);
+        }
+
+        let local_color_table = match self.global_color_table {
+            Some(global) if global == frame.color_table => None,
+            _ => Some(&frame.color_table),
+        };
+        let table_size = ColorTable::calculate_size(frame.color_table.size_flag());
+        if let Some(index) = frame.indices.iter().find(|i| **i as usize >= table_size) {
+            return Err(GIFEncodeError::ColorIndexOutOfBounds(*index));
+        }
+
+        self.write_header()?;
+
+        // without a graphic control extension a decoder assumes these settings
+        let needs_control = frame.delay_time != 0
+            || frame.disposal_method != DisposalMethod::DoNotDispose
+            || frame.user_input
+            || frame.transparent_color_index.is_some();
+        if needs_control {
+            GraphicControlExtension::new(
+                frame.delay_time,
+                frame.disposal_method,
+                frame.user_input,
+                frame.transparent_color_index,
+            )
+            .write(&mut self.writer)?;
+        }
+
+        ImageDescriptor::new(
+            ImagePosition {
+                left: frame.left,
+                top: frame.top,
+            },
+            (frame.width, frame.height),
+            local_color_table,
+        )
+        .write(&mut self.writer)?;
+        if let Some(table) = local_color_table {
+            table.write(&mut self.writer)?;
+        }
+
+        // the code size must be able to hold every index of the (padded) color table
+        let minimum_code_size = (frame.color_table.size_flag() + 1).max(2);
+        self.writer.write_all(&[minimum_code_size])?;
+        let data = LZWEncoder::encode(&frame.indices, minimum_code_size)?;
+        write_subblock(&mut self.writer, &data)?;
+        Ok(())
+    }
+
+    /// Writes the trailer and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, GIFEncodeError> {
+        self.write_header()?;
+        self.writer.write_all(&[BlockSeparator::Trailer as u8])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl GIFFrame {
+    /// Creates a frame covering the whole image by mapping every pixel to the nearest color of
+    /// `palette`.
+    pub fn from_image(
+        image: &ImageBuffer<RGB<u8>, Vec<u8>>,
+        palette: &ColorTable,
+    ) -> Result<Self, GIFEncodeError> {
+        let (width, height) = image.dimensions();
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(GIFEncodeError::ImageTooLarge { width, height });
+        }
+
+        Ok(GIFFrame {
+            left: 0,
+            top: 0,
+            width: width as u16,
+            height: height as u16,
+            indices: image
+                .iter()
+                .map(|(_, _, color)| palette.nearest_index(*color))
+                .collect(),
+            color_table: *palette,
+            delay_time: 0,
+            disposal_method: DisposalMethod::DoNotDispose,
+            user_input: false,
+            transparent_color_index: None,
+        })
+    }
+}
+
+/// Encodes a single image as gif with `palette` as global color table. Colors that are not in
+/// the palette are replaced by the nearest color of the palette.
+pub fn encode_image<W: Write>(
+    writer: W,
+    image: &ImageBuffer<RGB<u8>, Vec<u8>>,
+    palette: &ColorTable,
+) -> Result<W, GIFEncodeError> {
+    let frame = GIFFrame::from_image(image, palette)?;
+    let mut encoder = GIFEncoder::new(writer, (frame.width, frame.height), Some(*palette));
+    encoder.write_frame(&frame)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        formats::gif::{
+            better_decoder::GIFDecoder,
+            gif::{GIFDecode, GIFImage, SingleGIF},
+        },
+        generic_image::GenericImageMut,
+    };
+
+    use super::*;
+
+    #[test]
+    fn encode_single_image() {
+        let colors = [
+            RGB([0, 0, 0]),
+            RGB([255, 255, 255]),
+            RGB([255, 0, 0]),
+            RGB([0, 255, 0]),
+            RGB([0, 0, 255]),
+        ];
+        let palette = ColorTable::from_colors(&colors);
+
+        let mut image = ImageBuffer::new(7, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                image.put_pixel(x, y, colors[((x * 3 + y) % 5) as usize]);
+            }
+        }
+        // not in the palette, is mapped to red
+        image.put_pixel(0, 0, RGB([200, 10, 10]));
+
+        let data = encode_image(Vec::new(), &image, &palette).unwrap();
+        let Ok(GIFImage::Single(SingleGIF { image: decoded, .. })) =
+            GIFDecoder::new(&data[..]).decode()
+        else {
+            panic!("expected a single image");
+        };
+
+        assert_eq!(decoded.dimensions(), (7, 5));
+        assert_eq!(*decoded.get_pixel(0, 0), RGB([255, 0, 0]));
+        for y in 0..5 {
+            for x in 0..7 {
+                if (x, y) != (0, 0) {
+                    assert_eq!(decoded.get_pixel(x, y), image.get_pixel(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reencode_animated_gif() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        let animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+
+        let mut encoder = GIFEncoder::new(
+            Vec::new(),
+            animation.logical_dim,
+            Some(animation.frames[0].color_table),
+        );
+        encoder.set_background_color_index(animation.background_color_index);
+        for comment in &animation.comments {
+            encoder.write_comment(comment).unwrap();
+        }
+        for frame in &animation.frames {
+            encoder.write_frame(frame).unwrap();
+        }
+        let data = encoder.finish().unwrap();
+
+        let reencoded = GIFDecoder::new(&data[..]).decode_frames().unwrap();
+        assert_eq!(reencoded.comments, animation.comments);
+        assert_eq!(reencoded.background_color, animation.background_color);
+        assert_eq!(reencoded.frames.len(), animation.frames.len());
+        for (frame, expected) in reencoded.frames.iter().zip(&animation.frames) {
+            assert_eq!((frame.left, frame.top), (expected.left, expected.top));
+            assert_eq!(
+                (frame.width, frame.height),
+                (expected.width, expected.height)
+            );
+            assert_eq!(frame.indices, expected.indices);
+            assert!(frame.color_table == expected.color_table);
+            assert_eq!(frame.delay_time, expected.delay_time);
+            assert_eq!(frame.disposal_method, expected.disposal_method);
+            assert_eq!(frame.user_input, expected.user_input);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_frames() {
+        let table = ColorTable::from_colors(&[RGB([0, 0, 0]), RGB([255, 255, 255])]);
+        let image = ImageBuffer::new(2, 2);
+        let mut frame = GIFFrame::from_image(&image, &table).unwrap();
+
+        let mut encoder = GIFEncoder::new(Vec::new(), (2, 1), Some(table));
+        assert!(matches!(
+            encoder.write_frame(&frame),
+            Err(GIFEncodeError::FrameOutOfBounds)
+        ));
+
+        let mut encoder = GIFEncoder::new(Vec::new(), (2, 2), Some(table));
+        frame.indices[3] = 7;
+        assert!(matches!(
+            encoder.write_frame(&frame),
+            Err(GIFEncodeError::ColorIndexOutOfBounds(7))
+        ));
+    }
+}
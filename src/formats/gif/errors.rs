@@ -5,23 +5,40 @@
 use core::fmt::Display;
 use std::{error::Error, io};
 
-use super::blocks::{
-    Block, ColorTableParseError, GraphicControlExtension, GraphicControlExtensionParseError,
-    HeaderParseError, LogicalScreenDescriptorParseError, TableBasedImageParseError,
+use crate::error::ImageError;
+
+use super::{
+    blocks::{
+        ApplicationExtension, ApplicationExtensionParseError, Block, ColorTableParseError,
+        CommentExtensionParseError, GraphicControlExtension, GraphicControlExtensionParseError,
+        HeaderParseError, LogicalScreenDescriptorParseError, PlainTextExtension,
+        PlainTextExtensionParseError, TableBasedImageParseError,
+    },
+    limits::Limit,
+    lzw::LZWEncodeError,
 };
 
 #[derive(Debug)]
 pub enum GIFParseError {
-    Io { reason: String, cause: io::Error },
+    Io {
+        reason: String,
+        cause: io::Error,
+    },
     UnknownSignature(String),
     UnknownVersion(String),
     UnexpectedBlockDiscriminant(u8),
     UnexpectedExtensionLabel(u8),
-    UnexpectedBlockSize { got: u8, expected: u8 },
+    UnexpectedBlockSize {
+        got: u8,
+        expected: u8,
+    },
     InvalidBlockTerminator,
     InvalidColorTable(String),
     InvalidLZWCode,
     ImageDataError,
+    InvalidPlainText,
+    /// The gif exceeds one of the `DecoderLimits`
+    LimitExceeded(Limit),
 }
 
 impl Display for GIFParseError {
@@ -43,12 +60,56 @@ impl Display for GIFParseError {
             GIFParseError::InvalidColorTable(_) => write!(f, "invalid color table"),
             GIFParseError::InvalidLZWCode => write!(f, "invalid lzw code"),
             GIFParseError::ImageDataError => write!(f, "invalid image data"),
+            GIFParseError::InvalidPlainText => write!(f, "plain text is not valid utf-8"),
+            GIFParseError::LimitExceeded(limit) => write!(f, "decoder limit exceeded: {}", limit),
         }
     }
 }
 
 impl Error for GIFParseError {}
 
+/// A problem the decoder recovered from in lenient mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeWarning {
+    /// The data stream ended without a trailer
+    MissingTrailer,
+    /// Decoding stopped at a broken block, which was treated as the end of the data stream
+    UnexpectedEnd { reason: String },
+    /// The image data of the frame is truncated or contains invalid lzw codes. Everything up to
+    /// the error was kept.
+    InvalidImageData { frame: usize, reason: String },
+    /// The image data of the frame is shorter than the frame. The missing pixels were filled
+    /// with the background color.
+    MissingPixels { frame: usize, count: usize },
+}
+
+impl Display for DecodeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeWarning::MissingTrailer => write!(f, "missing trailer"),
+            DecodeWarning::UnexpectedEnd { reason } => {
+                write!(f, "data stream ended early: {}", reason)
+            }
+            DecodeWarning::InvalidImageData { frame, reason } => {
+                write!(f, "invalid image data in frame {}: {}", frame, reason)
+            }
+            DecodeWarning::MissingPixels { frame, count } => {
+                write!(f, "frame {} is missing {} pixels", frame, count)
+            }
+        }
+    }
+}
+
+impl From<GIFParseError> for ImageError {
+    fn from(value: GIFParseError) -> Self {
+        match value {
+            GIFParseError::Io { cause, .. } => ImageError::IoError(cause),
+            GIFParseError::LimitExceeded(_) => ImageError::Limits,
+            _ => ImageError::Decoding,
+        }
+    }
+}
+
 impl From<HeaderParseError> for GIFParseError {
     fn from(value: HeaderParseError) -> Self {
         match value {
@@ -135,6 +196,118 @@ impl From<GraphicControlExtensionParseError> for GIFParseError {
     }
 }
 
+impl From<ApplicationExtensionParseError> for GIFParseError {
+    fn from(value: ApplicationExtensionParseError) -> Self {
+        match value {
+            ApplicationExtensionParseError::Io(error) => GIFParseError::Io {
+                reason: "io error (likely EOF) during application extension reading/parsing"
+                    .to_string(),
+                cause: error,
+            },
+            ApplicationExtensionParseError::InvalidBlockSize(got) => {
+                GIFParseError::UnexpectedBlockSize {
+                    got,
+                    expected: ApplicationExtension::BLOCK_SIZE as u8,
+                }
+            }
+        }
+    }
+}
+
+impl From<CommentExtensionParseError> for GIFParseError {
+    fn from(value: CommentExtensionParseError) -> Self {
+        match value {
+            CommentExtensionParseError::Io(error) => GIFParseError::Io {
+                reason: "io error (likely EOF) during comment extension reading".to_string(),
+                cause: error,
+            },
+        }
+    }
+}
+
+impl From<PlainTextExtensionParseError> for GIFParseError {
+    fn from(value: PlainTextExtensionParseError) -> Self {
+        match value {
+            PlainTextExtensionParseError::Io(error) => GIFParseError::Io {
+                reason: "io error (likely EOF) during plain text extension reading".to_string(),
+                cause: error,
+            },
+            PlainTextExtensionParseError::InvalidBlockSize { found, .. } => {
+                GIFParseError::UnexpectedBlockSize {
+                    got: found as u8,
+                    expected: PlainTextExtension::BLOCK_SIZE as u8,
+                }
+            }
+            PlainTextExtensionParseError::InvalidASCII => GIFParseError::InvalidPlainText,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GIFEncodeError {
+    Io(io::Error),
+    /// The image does not fit into the 16 bit dimensions of a gif
+    ImageTooLarge {
+        width: u32,
+        height: u32,
+    },
+    /// The frame reaches outside of the logical screen
+    FrameOutOfBounds,
+    /// The number of color indices does not match the size of the frame
+    InvalidFrameData,
+    /// A color index points outside of the color table of the frame
+    ColorIndexOutOfBounds(u8),
+}
+
+impl Display for GIFEncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GIFEncodeError::Io(err) => write!(f, "io error: {}", err),
+            GIFEncodeError::ImageTooLarge { width, height } => write!(
+                f,
+                "image of {}x{} is too large for a gif (max {}x{})",
+                width,
+                height,
+                u16::MAX,
+                u16::MAX
+            ),
+            GIFEncodeError::FrameOutOfBounds => write!(f, "frame outside of the logical screen"),
+            GIFEncodeError::InvalidFrameData => {
+                write!(f, "frame data does not match the frame size")
+            }
+            GIFEncodeError::ColorIndexOutOfBounds(index) => {
+                write!(f, "color index {} outside of the color table", index)
+            }
+        }
+    }
+}
+
+impl Error for GIFEncodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GIFEncodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LZWEncodeError> for GIFEncodeError {
+    fn from(value: LZWEncodeError) -> Self {
+        match value {
+            LZWEncodeError::Io(err) => GIFEncodeError::Io(err),
+            LZWEncodeError::ValueOutOfRange(index) => GIFEncodeError::ColorIndexOutOfBounds(index),
+            // the encoder only uses minimum code sizes from 2 to 8
+            LZWEncodeError::InvalidMinimumCodeSize => GIFEncodeError::InvalidFrameData,
+        }
+    }
+}
+
+impl From<io::Error> for GIFEncodeError {
+    fn from(value: io::Error) -> Self {
+        GIFEncodeError::Io(value)
+    }
+}
+
 #[derive(Debug)]
 pub enum GIFDecodeError {
     /// The decode could not complete due to an io error. Most likely a ErrorKind::UnexpectedEOF
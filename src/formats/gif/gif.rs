@@ -2,20 +2,14 @@
 
 use std::fmt::Display;
 
-use crate::{colors::rgb::RGB, image_buffer::ImageBuffer};
+use crate::{colors::rgb::RGB, generic_image::GenericImageMut, image_buffer::ImageBuffer};
 
 use super::{
+    blocks::{ApplicationExtension, ColorTable, ColorTableLookup, CommentExtension},
     consts::{GIF_CONST_VERSION_87A, GIF_CONST_VERSION_89A},
-    errors::GIFParseError,
+    errors::{DecodeWarning, GIFParseError},
 };
 
-enum GIFFrameDisposal {
-    Unspecified,
-    NoDisposal,
-    RestoreBackground,
-    RestorePrevious,
-}
-
 /// GIF Image either Single Image Buffer or GIF Image Animation
 /// TODO: Implement MultiImageBuffer and use it here
 pub enum GIFImage {
@@ -28,13 +22,88 @@ pub trait GIFDecode {
     fn decode(self) -> Result<GIFImage, GIFParseError>;
 }
 
-type SingleGIF = ImageBuffer<RGB<u8>, Vec<u8>>;
+/// A gif with a single image, composited onto the logical screen.
+#[derive(Debug, Clone)]
+pub struct SingleGIF {
+    pub image: ImageBuffer<RGB<u8>, Vec<u8>>,
+    /// Application extensions other than the looping extensions (e.g. XMP data) in the order
+    /// they appear in the data stream
+    pub application_extensions: Vec<ApplicationExtension>,
+    /// All comment extensions in the order they appear in the data stream
+    pub comments: Vec<CommentExtension>,
+    /// The problems recovered from in lenient mode
+    pub warnings: Vec<DecodeWarning>,
+}
 
+/// A gif with more than one image. The frames are kept as they are stored in the data stream,
+/// i.e. every frame only covers its own sub rectangle of the logical screen.
+#[derive(Debug, Clone)]
 pub struct MultiGIF {
-    images: Vec<ImageBuffer<RGB<u8>, Vec<u8>>>,
-    delay_time: u16,
-    frame_disposal: GIFFrameDisposal,
-    user_input: bool,
+    /// width and height of the logical screen all frames are placed on
+    pub logical_dim: (u16, u16),
+    /// Index into the global color table for the background color
+    pub background_color_index: u8,
+    /// The background color looked up in the global color table. Black if there is none.
+    pub background_color: RGB<u8>,
+    pub frames: Vec<GIFFrame>,
+    /// `None` if there is no looping extension, i.e. the animation is shown once
+    pub loop_count: Option<LoopCount>,
+    /// Application extensions other than the looping extensions (e.g. XMP data) in the order
+    /// they appear in the data stream
+    pub application_extensions: Vec<ApplicationExtension>,
+    /// All comment extensions in the order they appear in the data stream
+    pub comments: Vec<CommentExtension>,
+    /// The problems recovered from in lenient mode
+    pub warnings: Vec<DecodeWarning>,
+}
+
+/// A single table based image together with the graphic control extension that preceded it.
+#[derive(Debug, Clone)]
+pub struct GIFFrame {
+    /// column of the logical screen the frame starts at
+    pub left: u16,
+    /// row of the logical screen the frame starts at
+    pub top: u16,
+    pub width: u16,
+    pub height: u16,
+    /// The color indices of the frame row by row. Always `width * height` long.
+    pub indices: Vec<u8>,
+    /// The color table active for this frame (Local > Global > Default)
+    pub color_table: ColorTable,
+    /// Time in hundredths of a second to wait before continuing with the next frame
+    pub delay_time: u16,
+    pub disposal_method: DisposalMethod,
+    /// whether the next frame should wait for user input (or the delay time, whatever is first)
+    pub user_input: bool,
+    /// Pixels with this color index are transparent and leave the canvas below untouched
+    pub transparent_color_index: Option<u8>,
+}
+
+impl GIFFrame {
+    /// Looks up every color index of the frame. The result has the size of the frame and not of
+    /// the logical screen.
+    pub fn to_image(&self) -> ImageBuffer<RGB<u8>, Vec<u8>> {
+        lookup_indices((self.width, self.height), &self.indices, &self.color_table)
+    }
+}
+
+/// Creates an image of `dim` by looking up the color `indices` (row by row) in `color_table`.
+pub(crate) fn lookup_indices(
+    (width, height): (u16, u16),
+    indices: &[u8],
+    color_table: &ColorTable,
+) -> ImageBuffer<RGB<u8>, Vec<u8>> {
+    let mut image = ImageBuffer::new(width as u32, height as u32);
+    for (i, color_index) in indices.iter().enumerate() {
+        let x = i % width as usize;
+        let y = i / width as usize;
+        image.put_pixel(
+            x as u32,
+            y as u32,
+            color_table.lookup_fallback(*color_index),
+        );
+    }
+    image
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -44,7 +113,7 @@ pub enum Version {
 }
 
 impl Version {
-    fn as_bytes(&self) -> &'static [u8; 3] {
+    pub fn as_bytes(&self) -> &'static [u8; 3] {
         match self {
             Version::Version87a => GIF_CONST_VERSION_87A,
             Version::Version89a => GIF_CONST_VERSION_89A,
@@ -61,6 +130,7 @@ impl Display for Version {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum DisposalMethod {
     NoDisposal = 0,
@@ -75,8 +145,25 @@ impl From<u8> for DisposalMethod {
             0 => DisposalMethod::NoDisposal,
             1 => DisposalMethod::DoNotDispose,
             2 => DisposalMethod::RestoreBackground,
-            3 => DisposalMethod::RestoreBackground,
+            3 => DisposalMethod::RestorePrevious,
             _ => DisposalMethod::NoDisposal,
         }
     }
 }
+
+/// How often an animation is played, as declared by a looping application extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoopCount {
+    Infinite,
+    /// The animation is repeated the given number of times after it has been shown once
+    Repeat(u16),
+}
+
+impl From<u16> for LoopCount {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => LoopCount::Infinite,
+            n => LoopCount::Repeat(n),
+        }
+    }
+}
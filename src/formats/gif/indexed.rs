@@ -0,0 +1,285 @@
+//! Decodes gifs into color indices instead of colors.
+//!
+//! Displays with a fixed palette (e.g. e-ink panels) have to map every color onto the palette.
+//! Keeping the indices together with their color table allows to do this once per color of the
+//! table instead of once per pixel, or to skip it entirely if the gif already uses the palette.
+//! Animations are remapped frame by frame with `MultiGIF::remap`.
+
+use std::io::Read;
+
+use crate::{colors::rgb::RGB, image_buffer::ImageBuffer};
+
+use super::{
+    better_decoder::GIFDecoder,
+    blocks::{ColorTable, ColorTableLookup, CommentExtension, MAX_COLOR_TABLE_SIZE},
+    errors::{DecodeWarning, GIFParseError},
+    gif::{GIFFrame, MultiGIF, lookup_indices},
+};
+
+/// An image of color indices into `color_table`.
+#[derive(Debug, Clone)]
+pub struct IndexedImage {
+    pub width: u16,
+    pub height: u16,
+    /// The color indices row by row. Always `width * height` long.
+    pub indices: Vec<u8>,
+    pub color_table: ColorTable,
+    /// The number of frames of the gif. Only the first one is part of the image, animations
+    /// have to be decoded with `decode_frames` instead.
+    pub frame_count: usize,
+    /// All comment extensions in the order they appear in the data stream
+    pub comments: Vec<CommentExtension>,
+    /// The problems recovered from in lenient mode
+    pub warnings: Vec<DecodeWarning>,
+}
+
+impl IndexedImage {
+    /// Maps the indices onto the nearest colors of `palette`, which becomes the new color table.
+    /// Does nothing if the image already uses `palette`.
+    pub fn remap(&mut self, palette: &ColorTable) {
+        if self.color_table == *palette {
+            return;
+        }
+
+        let mapping = self.color_table.nearest_indices(palette);
+        for index in self.indices.iter_mut() {
+            *index = mapping[*index as usize];
+        }
+        self.color_table = *palette;
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frame_count > 1
+    }
+
+    /// Looks up every color index.
+    pub fn to_image(&self) -> ImageBuffer<RGB<u8>, Vec<u8>> {
+        lookup_indices((self.width, self.height), &self.indices, &self.color_table)
+    }
+}
+
+impl<R: Read> GIFDecoder<'_, R> {
+    /// Decodes the first image of the gif onto the logical screen without looking up its colors.
+    /// Returns `None` if the gif has no image.
+    ///
+    /// The color table is the one active for the image. Pixels of the logical screen the image
+    /// does not cover and transparent pixels get the background color: its index if the image
+    /// uses the global color table, otherwise the nearest color of the image's table.
+    ///
+    /// The rest of the data stream is still read for its comments and to count the frames, see
+    /// `IndexedImage::is_animated`.
+    pub fn decode_indexed(mut self) -> Result<Option<IndexedImage>, GIFParseError> {
+        let Some(frame) = self.next_frame()? else {
+            return Ok(None);
+        };
+
+        let (width, height) = self.logical_dim();
+        let background_index = self.background_index_for(&frame.color_table);
+
+        let mut indices = vec![background_index; width as usize * height as usize];
+        // the frame may reach outside of the logical screen and is clipped
+        let right = (frame.left as usize + frame.width as usize).min(width as usize);
+        let bottom = (frame.top as usize + frame.height as usize).min(height as usize);
+        for y in frame.top as usize..bottom {
+            for x in frame.left as usize..right {
+                let index =
+                    (y - frame.top as usize) * frame.width as usize + (x - frame.left as usize);
+                match frame.indices.get(index) {
+                    Some(color_index) if Some(*color_index) == frame.transparent_color_index => {}
+                    Some(color_index) => indices[y * width as usize + x] = *color_index,
+                    None => {}
+                }
+            }
+        }
+
+        let mut frame_count = 1;
+        while self.next_frame()?.is_some() {
+            frame_count += 1;
+        }
+
+        Ok(Some(IndexedImage {
+            width,
+            height,
+            indices,
+            color_table: frame.color_table,
+            frame_count,
+            comments: self.comments().to_vec(),
+            warnings: self.warnings().to_vec(),
+        }))
+    }
+}
+
+impl GIFFrame {
+    /// Maps the indices onto the nearest colors of `palette` like `IndexedImage::remap`.
+    ///
+    /// Transparent pixels get the index after the colors of `palette`, so they stay transparent
+    /// and the color table becomes `palette` with the transparent color appended. A full palette
+    /// has no index left for them, they are then mapped like all other pixels and the frame
+    /// becomes opaque.
+    pub fn remap(&mut self, palette: &ColorTable) {
+        if self.color_table == *palette {
+            return;
+        }
+
+        let mapping = self.color_table.nearest_indices(palette);
+        let transparent = match self.transparent_color_index {
+            Some(index) if palette.size < MAX_COLOR_TABLE_SIZE => Some((index, palette.size as u8)),
+            _ => None,
+        };
+        for index in self.indices.iter_mut() {
+            *index = match transparent {
+                Some((from, to)) if *index == from => to,
+                _ => mapping[*index as usize],
+            };
+        }
+
+        let mut color_table = *palette;
+        if let Some((from, to)) = transparent {
+            color_table.data[to as usize] = self.color_table.lookup_fallback(from);
+            color_table.size += 1;
+        }
+        self.color_table = color_table;
+        self.transparent_color_index = transparent.map(|(_, to)| to);
+    }
+}
+
+impl MultiGIF {
+    /// Remaps every frame onto `palette` (see `GIFFrame::remap`). The background color becomes
+    /// the nearest color of `palette`.
+    pub fn remap(&mut self, palette: &ColorTable) {
+        for frame in self.frames.iter_mut() {
+            frame.remap(palette);
+        }
+        self.background_color_index = palette.nearest_index(self.background_color);
+        self.background_color = palette.lookup_fallback(self.background_color_index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        formats::gif::{
+            encoding::GIFEncoder,
+            gif::{GIFDecode, GIFImage, SingleGIF},
+        },
+        generic_image::GenericImage,
+    };
+
+    use super::*;
+
+    const BLACK: RGB<u8> = RGB([0, 0, 0]);
+    const WHITE: RGB<u8> = RGB([255, 255, 255]);
+
+    #[test]
+    fn decode_indexed_gif() {
+        let gif_data = include_bytes!("../../../test-assets/simplest.gif");
+        let indexed = GIFDecoder::new(&gif_data[..])
+            .decode_indexed()
+            .unwrap()
+            .unwrap();
+        let Ok(GIFImage::Single(SingleGIF { image, .. })) = GIFDecoder::new(&gif_data[..]).decode()
+        else {
+            panic!("expected a single image");
+        };
+
+        assert_eq!(indexed.indices.len(), image.as_container().len() / 3);
+        assert_eq!(indexed.to_image().as_container(), image.as_container());
+    }
+
+    #[test]
+    fn transparent_pixels_get_the_background() {
+        let gif_data = include_bytes!("../../../test-assets/transparent.gif");
+        let indexed = GIFDecoder::new(&gif_data[..])
+            .decode_indexed()
+            .unwrap()
+            .unwrap();
+        let animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+
+        let canvas = &animation.composite_frames()[0];
+        assert_eq!(indexed.to_image().as_container(), canvas.as_container());
+        // the second frame is only counted
+        assert_eq!(indexed.frame_count, 2);
+        assert!(indexed.is_animated());
+    }
+
+    #[test]
+    fn decode_indexed_keeps_comments_after_the_image() {
+        let palette = ColorTable::from_colors(&[BLACK, WHITE]);
+        let frame = GIFFrame::from_image(&ImageBuffer::new(2, 2), &palette).unwrap();
+        let mut encoder = GIFEncoder::new(Vec::new(), (2, 2), Some(palette));
+        encoder.write_frame(&frame).unwrap();
+        encoder
+            .write_comment(&CommentExtension::new("source"))
+            .unwrap();
+        let gif_data = encoder.finish().unwrap();
+
+        let indexed = GIFDecoder::new(&gif_data[..])
+            .decode_indexed()
+            .unwrap()
+            .unwrap();
+        assert_eq!(indexed.frame_count, 1);
+        assert_eq!(indexed.comments[0].text().unwrap(), "source");
+        assert!(indexed.warnings.is_empty());
+    }
+
+    #[test]
+    fn remap_animation_onto_palette() {
+        let gif_data = include_bytes!("../../../test-assets/transparent.gif");
+        let mut animation = GIFDecoder::new(&gif_data[..]).decode_frames().unwrap();
+        let display = ColorTable::from_colors(&[WHITE, RGB([255, 0, 0]), BLACK]);
+
+        let expected = animation
+            .composite_frames()
+            .iter()
+            .map(|canvas| {
+                let indices = canvas
+                    .iter()
+                    .map(|(_, _, color)| display.nearest_index(*color))
+                    .collect::<Vec<_>>();
+                let (width, height) = canvas.dimensions();
+                lookup_indices((width as u16, height as u16), &indices, &display)
+            })
+            .collect::<Vec<_>>();
+
+        animation.remap(&display);
+        // transparent pixels keep showing the frame below
+        assert_eq!(animation.frames[0].transparent_color_index, Some(3));
+        assert_eq!(animation.frames[0].color_table.size, 4);
+
+        for (canvas, expected) in animation.composite_frames().iter().zip(&expected) {
+            assert_eq!(canvas.as_container(), expected.as_container());
+        }
+    }
+
+    #[test]
+    fn remap_onto_palette() {
+        let mut indexed = IndexedImage {
+            width: 2,
+            height: 2,
+            // the last index is outside of the table and shows black
+            indices: vec![0, 1, 2, 3],
+            color_table: ColorTable::from_colors(&[
+                RGB([250, 240, 245]),
+                RGB([10, 30, 5]),
+                RGB([200, 0, 0]),
+            ]),
+            frame_count: 1,
+            comments: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let display = ColorTable::from_colors(&[WHITE, RGB([255, 0, 0]), BLACK]);
+
+        assert_eq!(
+            indexed.color_table.nearest_indices(&display)[..3],
+            [0, 2, 1]
+        );
+
+        indexed.remap(&display);
+        assert_eq!(indexed.indices, [0, 2, 1, 2]);
+        assert!(indexed.color_table == display);
+
+        // already on the palette
+        indexed.remap(&display);
+        assert_eq!(indexed.indices, [0, 2, 1, 2]);
+    }
+}
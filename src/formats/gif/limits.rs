@@ -0,0 +1,73 @@
+//! Resource limits for decoding untrusted gifs.
+//!
+//! The sizes in a gif are attacker controlled: a few bytes can declare a 65535x65535 logical
+//! screen or an lzw stream that expands into gigabytes. The decoder checks these limits before it
+//! allocates anything based on them.
+
+use std::fmt::Display;
+
+/// The limits the decoder enforces. Exceeding any of them aborts decoding with
+/// `GIFParseError::LimitExceeded`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecoderLimits {
+    /// Maximum width of the logical screen and of every frame
+    pub max_width: u16,
+    /// Maximum height of the logical screen and of every frame
+    pub max_height: u16,
+    /// Maximum number of frames (images and plain texts) kept in memory. Streaming frame by frame
+    /// is not limited.
+    pub max_frames: usize,
+    /// Maximum number of color indices of all frames kept in memory together. Streaming frame by
+    /// frame is not limited.
+    pub max_decoded_bytes: usize,
+    /// Maximum number of pixels of a single frame. The image data of a frame never decodes into
+    /// more color indices than the frame has pixels.
+    pub max_frame_pixels: usize,
+}
+
+impl DecoderLimits {
+    /// No limits at all, only for trusted input.
+    pub fn unlimited() -> Self {
+        DecoderLimits {
+            max_width: u16::MAX,
+            max_height: u16::MAX,
+            max_frames: usize::MAX,
+            max_decoded_bytes: usize::MAX,
+            max_frame_pixels: usize::MAX,
+        }
+    }
+}
+
+/// Fits into the memory of small boards like the Raspberry Pi Zero (512 MiB): a frame is at most
+/// 4096x4096 and all frames kept together at most 64 MiB, e.g. 170 frames of 800x480.
+impl Default for DecoderLimits {
+    fn default() -> Self {
+        DecoderLimits {
+            max_width: 4096,
+            max_height: 4096,
+            max_frames: 10_000,
+            max_decoded_bytes: 64 * 1024 * 1024,
+            max_frame_pixels: 4096 * 4096,
+        }
+    }
+}
+
+/// The limit that was exceeded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit {
+    Dimensions,
+    FrameCount,
+    DecodedBytes,
+    FramePixels,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Dimensions => write!(f, "dimensions"),
+            Limit::FrameCount => write!(f, "frame count"),
+            Limit::DecodedBytes => write!(f, "total decoded bytes"),
+            Limit::FramePixels => write!(f, "frame pixels"),
+        }
+    }
+}
@@ -3,12 +3,15 @@
 // This lets us easily safe memory and still good decompression rates and should be fine enough for gif decoding.
 
 use std::{
+    collections::HashMap,
     error::Error,
     fmt::Display,
-    io::{self, BufRead},
+    io::{self, BufRead, Write},
 };
 
-use crate::formats::bits::{BitReader, BitsReadError, LittleEndianReader};
+use crate::formats::bits::{
+    BitReader, BitWriter, BitsReadError, LittleEndianReader, LittleEndianWriter,
+};
 
 /// The value to use on codes. Due to variable length coding anywhere from 2-12 bits are used.
 type Code = u16;
@@ -20,6 +23,7 @@ pub enum LZWDecodeError {
     TooLargeCode { found: Code, table_size: Code },
     PrefixMismatch { reason: &'static str },
     BitRead { source: String, cause: io::Error },
+    Io(io::Error),
 }
 
 impl Display for LZWDecodeError {
@@ -36,6 +40,7 @@ impl Display for LZWDecodeError {
                 write!(f, "internal state mismatch {}", reason)
             }
             LZWDecodeError::BitRead { source, cause: _ } => write!(f, "bit read error {}", source),
+            LZWDecodeError::Io(err) => write!(f, "io error {}", err),
         }
     }
 }
@@ -44,6 +49,7 @@ impl Error for LZWDecodeError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             LZWDecodeError::BitRead { source: _, cause } => Some(cause),
+            LZWDecodeError::Io(err) => Some(err),
             _ => None,
         }
     }
@@ -62,12 +68,14 @@ impl From<LZWDecodeError> for io::Error {
             } => io::Error::new(io::ErrorKind::InvalidData, value.to_string()),
             LZWDecodeError::PrefixMismatch { reason: _ } => io::Error::other(value.to_string()),
             LZWDecodeError::BitRead { source: _, cause } => cause,
+            LZWDecodeError::Io(err) => err,
         }
     }
 }
 
 const MAX_TABLE_SIZE: usize = 4096;
 const MAX_CODE_VALUE: Code = 0xFFF; // 12 bit is the max code
+const MAX_CODE_BITS: u8 = 12;
 
 // codebook logic
 // To safe memory and keep high speeds we use references to the already decoded output of old codes.
@@ -129,8 +137,7 @@ impl CodeBook {
         }
     }
     fn is_full(&self) -> bool {
-        self.next_index
-            .is_some_and(|x| x >= MAX_CODE_VALUE as usize)
+        self.next_index.is_some_and(|x| x > MAX_CODE_VALUE as usize)
     }
     fn clear(&mut self) {
         self.next_index = None;
@@ -296,7 +303,7 @@ impl LZW for LZWDecoder {
 
             // We have decoded the current code. Now move on the next and start define it as the
             // current incomplete one.
-            if codebook.increment_next_index() {
+            if codebook.increment_next_index() && current_code_bits < MAX_CODE_BITS {
                 // the new code needs
                 current_code_bits += 1;
             }
@@ -312,6 +319,344 @@ impl LZW for LZWDecoder {
     }
 }
 
+#[derive(Debug)]
+pub enum LZWEncodeError {
+    InvalidMinimumCodeSize,
+    /// The value cannot be represented with the minimum code size
+    ValueOutOfRange(u8),
+    Io(io::Error),
+}
+
+impl Display for LZWEncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LZWEncodeError::InvalidMinimumCodeSize => {
+                write!(f, "minimum_code_size <= 1 or >= 11 invalid")
+            }
+            LZWEncodeError::ValueOutOfRange(value) => {
+                write!(f, "value {} does not fit the minimum code size", value)
+            }
+            LZWEncodeError::Io(err) => write!(f, "io error {}", err),
+        }
+    }
+}
+
+impl Error for LZWEncodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LZWEncodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LZWEncodeError {
+    fn from(value: io::Error) -> Self {
+        LZWEncodeError::Io(value)
+    }
+}
+
+/// What an `IncrementalLZWDecoder` call did.
+#[derive(Debug)]
+pub struct LZWProgress {
+    /// Number of input bytes consumed
+    pub consumed: usize,
+    /// Number of bytes written to the output
+    pub written: usize,
+    pub status: Result<LZWStatus, LZWDecodeError>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LZWStatus {
+    /// The input is used up or the output is full, decoding continues with the next call
+    Ok,
+    /// The end of information code was read, no further data is decoded
+    Done,
+}
+
+/// An lzw decoder that decodes chunk by chunk into buffers of the caller.
+///
+/// In contrast to `LZWDecoder` neither the compressed data nor the output have to be available as
+/// a whole: the input can be fed in sub-block by sub-block and the output written e.g. row by row.
+/// Instead of referencing the output, every code stores its prefix code, so a word is
+/// reconstructed by following the prefixes from its last byte to the first. Words that do not
+/// fit into the output are kept and written first on the next call.
+pub struct IncrementalLZWDecoder {
+    minimum_code_size: u8,
+    clear_code: Code,
+    end_of_information_code: Code,
+    code_bits: u8,
+    /// the code the next word is added as, `MAX_TABLE_SIZE` once the table is full
+    next_code: Code,
+    /// the previously decoded code, `None` at the start and after a clear code
+    previous: Option<Code>,
+    /// `word(code) = word(prefix[code]) ++ suffix[code]`
+    prefix: [Code; MAX_TABLE_SIZE],
+    suffix: [u8; MAX_TABLE_SIZE],
+    /// the first byte of each word
+    first: [u8; MAX_TABLE_SIZE],
+    /// the length of each word
+    length: [u16; MAX_TABLE_SIZE],
+    /// read bits that do not form a complete code yet, the oldest in the lowest bits
+    bit_buffer: u32,
+    bit_count: u8,
+    /// the part of the last word that did not fit into the output
+    pending: [u8; MAX_TABLE_SIZE],
+    pending_range: (usize, usize),
+    done: bool,
+}
+
+impl IncrementalLZWDecoder {
+    pub fn new(minimum_code_size: u8) -> Result<Self, LZWDecodeError> {
+        if minimum_code_size <= 1 || minimum_code_size >= 11 {
+            return Err(LZWDecodeError::InvalidMinimumCodeSize);
+        }
+
+        let clear_code: Code = 1 << minimum_code_size;
+        let mut decoder = IncrementalLZWDecoder {
+            minimum_code_size,
+            clear_code,
+            end_of_information_code: clear_code + 1,
+            code_bits: minimum_code_size + 1,
+            next_code: clear_code + 2,
+            previous: None,
+            prefix: [0; MAX_TABLE_SIZE],
+            suffix: [0; MAX_TABLE_SIZE],
+            first: [0; MAX_TABLE_SIZE],
+            length: [0; MAX_TABLE_SIZE],
+            bit_buffer: 0,
+            bit_count: 0,
+            pending: [0; MAX_TABLE_SIZE],
+            pending_range: (0, 0),
+            done: false,
+        };
+        for code in 0..clear_code {
+            decoder.suffix[code as usize] = code as u8;
+            decoder.first[code as usize] = code as u8;
+            decoder.length[code as usize] = 1;
+        }
+        Ok(decoder)
+    }
+
+    /// Whether the end of information code was read.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn clear(&mut self) {
+        self.code_bits = self.minimum_code_size + 1;
+        self.next_code = self.clear_code + 2;
+        self.previous = None;
+    }
+
+    /// Decodes as much of `input` into `output` as possible.
+    ///
+    /// Returns once the input is used up, the output is full, the end of information code was
+    /// read or an error occurred. Bits of an incomplete code at the end of `input` are consumed
+    /// and kept for the next call.
+    pub fn decode(&mut self, input: &[u8], output: &mut [u8]) -> LZWProgress {
+        let mut progress = LZWProgress {
+            consumed: 0,
+            written: 0,
+            status: Ok(LZWStatus::Ok),
+        };
+
+        loop {
+            let (start, end) = self.pending_range;
+            if start < end {
+                let count = (end - start).min(output.len() - progress.written);
+                output[progress.written..progress.written + count]
+                    .copy_from_slice(&self.pending[start..start + count]);
+                progress.written += count;
+                self.pending_range.0 += count;
+                if self.pending_range.0 < end {
+                    return progress;
+                }
+            }
+
+            if self.done {
+                progress.status = Ok(LZWStatus::Done);
+                return progress;
+            }
+            if progress.written == output.len() {
+                return progress;
+            }
+
+            while self.bit_count < self.code_bits {
+                let Some(byte) = input.get(progress.consumed) else {
+                    return progress;
+                };
+                self.bit_buffer |= (*byte as u32) << self.bit_count;
+                self.bit_count += 8;
+                progress.consumed += 1;
+            }
+            let code = (self.bit_buffer & ((1 << self.code_bits) - 1)) as Code;
+            self.bit_buffer >>= self.code_bits;
+            self.bit_count -= self.code_bits;
+
+            if code == self.clear_code {
+                self.clear();
+                continue;
+            }
+            if code == self.end_of_information_code {
+                self.done = true;
+                continue;
+            }
+
+            let defined = match self.previous {
+                None => code < self.clear_code,
+                Some(_) => code <= self.next_code && (code as usize) < MAX_TABLE_SIZE,
+            };
+            if !defined {
+                progress.status = Err(LZWDecodeError::TooLargeCode {
+                    found: code,
+                    table_size: self.next_code,
+                });
+                return progress;
+            }
+
+            if let Some(previous) = self.previous
+                && (self.next_code as usize) < MAX_TABLE_SIZE
+            {
+                // a code that is not defined yet is the previous word extended by its own
+                // first byte
+                let first = if code < self.next_code {
+                    self.first[code as usize]
+                } else {
+                    self.first[previous as usize]
+                };
+                let new = self.next_code as usize;
+                self.prefix[new] = previous;
+                self.suffix[new] = first;
+                self.first[new] = self.first[previous as usize];
+                self.length[new] = self.length[previous as usize] + 1;
+
+                self.next_code += 1;
+                if self.next_code == 1 << self.code_bits && self.code_bits < MAX_CODE_BITS {
+                    self.code_bits += 1;
+                }
+            }
+            self.previous = Some(code);
+
+            let length = self.length[code as usize] as usize;
+            if length <= output.len() - progress.written {
+                let word = &mut output[progress.written..progress.written + length];
+                write_word(&self.prefix, &self.suffix, code, word);
+                progress.written += length;
+            } else {
+                write_word(
+                    &self.prefix,
+                    &self.suffix,
+                    code,
+                    &mut self.pending[..length],
+                );
+                self.pending_range = (0, length);
+            }
+        }
+    }
+}
+
+/// Writes the word of `code` into `target`, which has exactly the length of the word.
+fn write_word(
+    prefix: &[Code; MAX_TABLE_SIZE],
+    suffix: &[u8; MAX_TABLE_SIZE],
+    mut code: Code,
+    target: &mut [u8],
+) {
+    for byte in target.iter_mut().rev() {
+        *byte = suffix[code as usize];
+        code = prefix[code as usize];
+    }
+}
+
+/// Variable code length lzw encoder producing the code stream `LZWDecoder` reads.
+///
+/// The stream starts with a clear code and ends with the end of information code. Codes start
+/// with `minimum_code_size + 1` bits and grow up to 12 bits. Once all 4096 codes are used a clear
+/// code is written and the code table starts over.
+pub struct LZWEncoder {}
+
+impl LZWEncoder {
+    pub fn encode(data: &[u8], minimum_code_size: u8) -> Result<Vec<u8>, LZWEncodeError> {
+        let mut output = Vec::new();
+        Self::encode_into(data, minimum_code_size, &mut output)?;
+        Ok(output)
+    }
+
+    /// Encodes `data` and writes the code stream to `writer` (not yet split into sub-blocks).
+    pub fn encode_into<W: Write>(
+        data: &[u8],
+        minimum_code_size: u8,
+        writer: W,
+    ) -> Result<(), LZWEncodeError> {
+        if minimum_code_size <= 1 || minimum_code_size >= 11 {
+            return Err(LZWEncodeError::InvalidMinimumCodeSize);
+        }
+
+        let clear_code: Code = 1 << minimum_code_size;
+        let end_of_information_code = clear_code + 1;
+        let mut bit_writer = LittleEndianWriter::new(writer);
+
+        // maps a known word (by its code) extended by one value to the code of the new word
+        let mut table: HashMap<(Code, u8), Code> = HashMap::new();
+        let mut next_code = end_of_information_code + 1;
+        let mut current_code_bits = minimum_code_size + 1;
+
+        bit_writer.write_n(clear_code, current_code_bits)?;
+
+        // the code of the longest word matching the input so far
+        let mut word: Option<Code> = None;
+        for &value in data {
+            if value as Code >= clear_code {
+                return Err(LZWEncodeError::ValueOutOfRange(value));
+            }
+
+            let Some(prefix) = word else {
+                word = Some(value as Code);
+                continue;
+            };
+
+            if let Some(code) = table.get(&(prefix, value)) {
+                word = Some(*code);
+                continue;
+            }
+
+            bit_writer.write_n(prefix, current_code_bits)?;
+
+            if (next_code as usize) < MAX_TABLE_SIZE {
+                table.insert((prefix, value), next_code);
+                next_code += 1;
+                // the decoder is one code behind, so the code size grows one code later
+                if next_code > (1 << current_code_bits) && current_code_bits < MAX_CODE_BITS {
+                    current_code_bits += 1;
+                }
+            } else {
+                bit_writer.write_n(clear_code, current_code_bits)?;
+                table.clear();
+                next_code = end_of_information_code + 1;
+                current_code_bits = minimum_code_size + 1;
+            }
+
+            word = Some(value as Code);
+        }
+
+        if let Some(code) = word {
+            bit_writer.write_n(code, current_code_bits)?;
+            // the decoder adds a code for the last word as well, which may grow the code size
+            // for the end of information code
+            if (next_code as usize) < MAX_TABLE_SIZE {
+                next_code += 1;
+                if next_code > (1 << current_code_bits) && current_code_bits < MAX_CODE_BITS {
+                    current_code_bits += 1;
+                }
+            }
+        }
+        bit_writer.write_n(end_of_information_code, current_code_bits)?;
+        bit_writer.flush()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -374,4 +719,155 @@ mod tests {
 
         assert_eq!(res.unwrap(), expected);
     }
+
+    #[test]
+    fn encode_lorem_ipsum() {
+        let data = include_bytes!("../../../test-assets/lorem_ipsum.txt");
+        let expected = include_bytes!("../../../test-assets/lorem_ipsum_encoded.bin");
+
+        let res = LZWEncoder::encode(data, 7).unwrap();
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn encode_4color_data() {
+        let data = [
+            1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 2, 2, 2, 2,
+            2, 1, 1, 1, 0, 0, 0, 0, 2, 2, 2,
+        ];
+
+        let res = LZWEncoder::encode(&data, 2).unwrap();
+        assert_eq!(
+            res,
+            [
+                0x8C, 0x2D, 0x99, 0x87, 0x2A, 0x1C, 0xDC, 0x33, 0xA0, 0x2, 0x55, 0x0
+            ]
+        );
+    }
+
+    /// xorshift, good enough to generate test data without a dependency
+    fn pseudo_random(seed: &mut u32) -> u32 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 17;
+        *seed ^= *seed << 5;
+        *seed
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let mut seed = 0x2545_F491;
+        for minimum_code_size in 2..=8u8 {
+            let max_value = 1u32 << minimum_code_size;
+            for length in [0, 1, 2, 255, 4096, 70_000] {
+                // alternate between noise (fills the table quickly) and long runs
+                let data: Vec<u8> = (0..length)
+                    .map(|i| {
+                        let value = if (i / 1000) % 2 == 0 {
+                            pseudo_random(&mut seed)
+                        } else {
+                            (i / 50) as u32
+                        };
+                        (value % max_value) as u8
+                    })
+                    .collect();
+
+                let encoded = LZWEncoder::encode(&data, minimum_code_size).unwrap();
+                let decoded = LZWDecoder::decode(&encoded[..], minimum_code_size).unwrap();
+                assert_eq!(
+                    decoded, data,
+                    "round trip failed for minimum code size {} and length {}",
+                    minimum_code_size, length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn encode_code_size_growth_before_end_of_information() {
+        // the last code fills the table up to 16 entries, so the end of information code
+        // already needs 5 bits
+        let mut data = vec![0u8; 48];
+        data[9] = 1;
+        let encoded = LZWEncoder::encode(&data, 2).unwrap();
+        assert_eq!(LZWDecoder::decode(&encoded[..], 2).unwrap(), data);
+    }
+
+    /// Decodes `data` feeding `input_chunk` bytes and taking `output_chunk` bytes at a time.
+    fn decode_incremental(
+        data: &[u8],
+        minimum_code_size: u8,
+        input_chunk: usize,
+        output_chunk: usize,
+    ) -> Result<Vec<u8>, LZWDecodeError> {
+        let mut decoder = IncrementalLZWDecoder::new(minimum_code_size)?;
+        let mut output = Vec::new();
+        let mut input = data;
+        let mut buf = vec![0u8; output_chunk];
+
+        while !decoder.is_done() {
+            let chunk = &input[..input.len().min(input_chunk)];
+            let progress = decoder.decode(chunk, &mut buf);
+            progress.status?;
+            if progress.consumed == 0 && progress.written == 0 && !decoder.is_done() {
+                return Err(LZWDecodeError::UnexpectedEOF);
+            }
+            input = &input[progress.consumed..];
+            output.extend_from_slice(&buf[..progress.written]);
+        }
+        Ok(output)
+    }
+
+    #[test]
+    fn incremental_decode_lorem_ipsum() {
+        let data = include_bytes!("../../../test-assets/lorem_ipsum_encoded.bin");
+        let expected = include_bytes!("../../../test-assets/lorem_ipsum.txt");
+
+        for (input_chunk, output_chunk) in [(1, 1), (3, 7), (255, 64), (data.len(), 100_000)] {
+            let decoded = decode_incremental(data, 7, input_chunk, output_chunk).unwrap();
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn incremental_decode_round_trip() {
+        let mut seed = 0x1234_5678;
+        for minimum_code_size in [2, 5, 8] {
+            let max_value = 1u32 << minimum_code_size;
+            // noise fills the table and forces clear codes, runs produce long words
+            let data: Vec<u8> = (0..20_000)
+                .map(|i| match (i / 2000) % 2 {
+                    0 => (pseudo_random(&mut seed) % max_value) as u8,
+                    _ => ((i / 700) as u32 % max_value) as u8,
+                })
+                .collect();
+            let encoded = LZWEncoder::encode(&data, minimum_code_size).unwrap();
+
+            let decoded = decode_incremental(&encoded, minimum_code_size, 17, 100).unwrap();
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn incremental_decode_invalid_code() {
+        // clear code, 0 and then code 7 which is not defined yet
+        let mut decoder = IncrementalLZWDecoder::new(2).unwrap();
+        let progress = decoder.decode(&[0b1100_0100, 0b0000_0001], &mut [0u8; 8]);
+        assert_eq!(progress.written, 1);
+        assert!(matches!(
+            progress.status,
+            Err(LZWDecodeError::TooLargeCode { found: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn encode_rejects_values_out_of_range() {
+        assert!(matches!(
+            LZWEncoder::encode(&[0, 1, 4], 2),
+            Err(LZWEncodeError::ValueOutOfRange(4))
+        ));
+        assert!(matches!(
+            LZWEncoder::encode(&[0], 1),
+            Err(LZWEncodeError::InvalidMinimumCodeSize)
+        ));
+    }
 }
@@ -1,10 +1,17 @@
 // According to gif89a specifications
+pub mod animation_encoder;
 pub mod better_decoder;
 pub mod blocks;
+pub mod compositor;
 pub mod consts;
 pub mod encoding;
 pub mod errors;
 pub mod gif;
+pub mod indexed;
+pub mod limits;
 pub mod lzw;
+pub mod plain_text;
+pub mod probe;
 pub mod ringbuf;
+pub mod stream;
 //pub mod old_blocks;
@@ -0,0 +1,226 @@
+//! Renders plain text extensions into color indices with a built-in 5x7 bitmap font.
+//!
+//! The gif spec leaves the font to the decoder. Every character cell is filled with a 5x7 glyph
+//! in a 6x8 box (one pixel of spacing to the right and bottom) scaled to the declared cell size.
+
+use super::blocks::PlainTextExtension;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// The glyph including the spacing to the next character and line
+const GLYPH_BOX: (usize, usize) = (GLYPH_WIDTH + 1, GLYPH_HEIGHT + 1);
+
+/// The printable ascii characters the font has glyphs for
+const FIRST_CHAR: char = ' ';
+const LAST_CHAR: char = '~';
+
+/// Glyphs for ' ' to '~'. Every byte is a column from left to right with the lowest bit being the
+/// top row.
+#[rustfmt::skip]
+const FONT: [[u8; GLYPH_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x14, 0x08, 0x3E, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+/// The glyph for `c`. Characters the font has no glyph for (which the spec does not allow
+/// anyway) are drawn as a space.
+fn glyph(c: char) -> &'static [u8; GLYPH_WIDTH] {
+    if (FIRST_CHAR..=LAST_CHAR).contains(&c) {
+        &FONT[c as usize - FIRST_CHAR as usize]
+    } else {
+        &FONT[0]
+    }
+}
+
+/// Renders the text of `extension` into color indices covering the whole text grid, row by row.
+///
+/// The characters fill the grid cell by cell from left to right and top to bottom. Characters
+/// that do not fit into the grid are dropped, as are partial cells at the right and bottom edge
+/// which keep the background color.
+pub fn render_plain_text(extension: &PlainTextExtension) -> Vec<u8> {
+    let (grid_width, grid_height) = extension.text_grid_dim();
+    let (grid_width, grid_height) = (grid_width as usize, grid_height as usize);
+    let (cell_width, cell_height) = extension.char_cell_dim();
+    let (cell_width, cell_height) = (cell_width as usize, cell_height as usize);
+    let foreground = extension.text_foreground_color_index();
+
+    let mut indices = vec![extension.text_background_color_index(); grid_width * grid_height];
+    if cell_width == 0 || cell_height == 0 {
+        return indices;
+    }
+
+    let columns = grid_width / cell_width;
+    let rows = grid_height / cell_height;
+
+    for (cell, c) in extension.text().chars().take(columns * rows).enumerate() {
+        let glyph = glyph(c);
+        let cell_left = (cell % columns) * cell_width;
+        let cell_top = (cell / columns) * cell_height;
+
+        for y in 0..cell_height {
+            // nearest neighbour scaling of the glyph box onto the cell
+            let glyph_y = y * GLYPH_BOX.1 / cell_height;
+            if glyph_y >= GLYPH_HEIGHT {
+                continue;
+            }
+            for x in 0..cell_width {
+                let glyph_x = x * GLYPH_BOX.0 / cell_width;
+                if glyph_x < GLYPH_WIDTH && glyph[glyph_x] & (1 << glyph_y) != 0 {
+                    indices[(cell_top + y) * grid_width + cell_left + x] = foreground;
+                }
+            }
+        }
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extension(grid: (u16, u16), cell: (u8, u8), text: &str) -> PlainTextExtension {
+        let mut data = vec![12];
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&grid.0.to_le_bytes());
+        data.extend_from_slice(&grid.1.to_le_bytes());
+        data.extend_from_slice(&[cell.0, cell.1, 1, 0]);
+        data.push(text.len() as u8);
+        data.extend_from_slice(text.as_bytes());
+        data.push(0);
+        PlainTextExtension::parse(&mut &data[..]).unwrap_or_else(|_| panic!())
+    }
+
+    fn rows(indices: &[u8], width: usize) -> Vec<String> {
+        indices
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .map(|i| if *i == 1 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn render_glyphs() {
+        // the third character does not fit into the grid
+        let indices = render_plain_text(&extension((12, 8), (6, 8), "Hi!"));
+        assert_eq!(
+            rows(&indices, 12),
+            [
+                "#...#...#...",
+                "#...#.......",
+                "#...#..##...",
+                "#####...#...",
+                "#...#...#...",
+                "#...#...#...",
+                "#...#..###..",
+                "............",
+            ]
+        );
+    }
+
+    #[test]
+    fn render_scaled_glyph() {
+        let indices = render_plain_text(&extension((12, 16), (12, 16), "-"));
+        let rows = rows(&indices, 12);
+        // the 1px high dash at row 3 is scaled to 2px
+        assert_eq!(rows[6], "##########..");
+        assert_eq!(rows[7], "##########..");
+        assert!(rows.iter().filter(|row| row.contains('#')).count() == 2);
+    }
+}
@@ -0,0 +1,160 @@
+//! Reads the metadata of a gif without decoding its image data.
+//!
+//! The image data is skipped sub-block by sub-block, so probing is cheap even for long animations.
+
+use std::{io::Read, time::Duration};
+
+use super::{
+    blocks::{
+        ApplicationExtension, Block, BlockLabel, BlockSeparator, ColorTable,
+        GraphicControlExtension, Header, ImageDescriptor, LogicalScreenDescriptor, TableBasedImage,
+        decoding::read_n_byte, decoding::skip_subblock,
+    },
+    errors::GIFParseError,
+    gif::{LoopCount, Version},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GIFMetadata {
+    pub version: Version,
+    /// width and height of the logical screen all frames are placed on
+    pub logical_dim: (u16, u16),
+    /// Number of graphic rendering blocks (table based images and plain text) in the data stream
+    pub frame_count: usize,
+    /// `None` if there is no looping extension, i.e. the animation is shown once
+    pub loop_count: Option<LoopCount>,
+    /// Sum of the delay times of all frames in hundredths of a second
+    pub total_delay: u64,
+}
+
+impl GIFMetadata {
+    /// The time a single run through all frames takes.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.total_delay.saturating_mul(10))
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frame_count > 1
+    }
+}
+
+/// Reads the header, logical screen descriptor and all extensions of a gif and skips the image
+/// data.
+///
+/// The grammar is checked less strictly than by the decoder. A file that probes fine can still
+/// fail to decode.
+pub fn probe<R: Read>(mut reader: R) -> Result<GIFMetadata, GIFParseError> {
+    let header = Header::parse(&mut reader)?;
+    let descriptor = LogicalScreenDescriptor::parse(&mut reader)?;
+    if descriptor.global_color_table_flag() {
+        ColorTable::try_from_reader(
+            &mut reader,
+            descriptor.global_color_table_size(),
+            descriptor.sort_flag(),
+        )?;
+    }
+
+    let mut metadata = GIFMetadata {
+        version: header.version,
+        logical_dim: (
+            descriptor.logical_screen_width,
+            descriptor.logical_screen_height,
+        ),
+        frame_count: 0,
+        loop_count: None,
+        total_delay: 0,
+    };
+    // the delay of a graphic control extension belongs to the graphic rendering block after it
+    let mut pending_delay = None;
+
+    loop {
+        let [discriminant] = read_n_byte::<_, 1>(&mut reader)?;
+        match BlockSeparator::try_from_u8(discriminant) {
+            Some(BlockSeparator::Image) => {
+                skip_table_based_image(&mut reader)?;
+                metadata.frame_count += 1;
+                metadata.total_delay += pending_delay.take().unwrap_or(0) as u64;
+            }
+            Some(BlockSeparator::Extension) => {
+                let [label] = read_n_byte::<_, 1>(&mut reader)?;
+                match BlockLabel::try_from_u8(label) {
+                    Some(BlockLabel::GraphicControlExtension) => {
+                        let control = GraphicControlExtension::parse(&mut reader)?;
+                        pending_delay = Some(control.delay_time());
+                    }
+                    Some(BlockLabel::ApplicationExtension) => {
+                        let extension = ApplicationExtension::parse(&mut reader)?;
+                        if let Some(loop_count) = extension.loop_count() {
+                            metadata.loop_count = Some(loop_count);
+                        }
+                    }
+                    Some(BlockLabel::PlainTextExtension) => {
+                        // the decoder renders plain text as a frame of its own
+                        skip_subblock(&mut reader)?;
+                        metadata.frame_count += 1;
+                        metadata.total_delay += pending_delay.take().unwrap_or(0) as u64;
+                    }
+                    _ => skip_subblock(&mut reader)?,
+                }
+            }
+            Some(BlockSeparator::Trailer) => break,
+            None => return Err(GIFParseError::UnexpectedBlockDiscriminant(discriminant)),
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Skips an image descriptor, its local color table and the lzw compressed image data.
+fn skip_table_based_image<R: Read>(reader: &mut R) -> Result<(), GIFParseError> {
+    let buf = read_n_byte::<_, { TableBasedImage::BLOCK_SIZE }>(reader)?;
+    let descriptor = ImageDescriptor::from(&buf);
+    if descriptor.local_color_table_flag() {
+        ColorTable::try_from_reader(
+            reader,
+            descriptor.local_color_table_size_flag(),
+            descriptor.sort_flag(),
+        )?;
+    }
+
+    // lzw minimum code size
+    read_n_byte::<_, 1>(reader)?;
+    skip_subblock(reader)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufReader};
+
+    use super::*;
+
+    #[test]
+    fn probe_animated_gif() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test-assets/animated.gif");
+        let metadata = probe(BufReader::new(File::open(path).unwrap())).unwrap();
+
+        assert_eq!(
+            metadata,
+            GIFMetadata {
+                version: Version::Version89a,
+                logical_dim: (4, 4),
+                frame_count: 3,
+                loop_count: Some(LoopCount::Infinite),
+                total_delay: 60,
+            }
+        );
+        assert_eq!(metadata.duration(), Duration::from_millis(600));
+    }
+
+    #[test]
+    fn probe_single_image() {
+        let gif_data = include_bytes!("../../../test-assets/simplest.gif");
+        let metadata = probe(&gif_data[..]).unwrap();
+
+        assert_eq!(metadata.frame_count, 1);
+        assert!(!metadata.is_animated());
+        assert_eq!(metadata.loop_count, None);
+        assert_eq!(metadata.total_delay, 0);
+    }
+}
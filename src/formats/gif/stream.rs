@@ -0,0 +1,172 @@
+//! Frame by frame decoding of gif animations.
+//!
+//! Decoding a long animation into a `MultiGIF` keeps every frame in memory. `GIFFrames` instead
+//! decodes and composites one frame at a time onto a single canvas that is reused between frames.
+
+use std::{borrow::Cow, io::Read};
+
+use crate::{colors::rgb::RGB, image_buffer::ImageBuffer};
+
+use super::{
+    better_decoder::GIFDecoder,
+    compositor::{CanvasPixel, Compositor},
+    errors::GIFParseError,
+    gif::DisposalMethod,
+};
+
+/// A frame composited onto the full logical screen together with its timing.
+#[derive(Debug, Clone)]
+pub struct CompositedFrame<'c, P: CanvasPixel> {
+    /// The full logical screen as it should be shown for this frame
+    pub image: Cow<'c, ImageBuffer<P, Vec<u8>>>,
+    /// Time in hundredths of a second to wait before continuing with the next frame
+    pub delay_time: u16,
+    pub disposal_method: DisposalMethod,
+    pub user_input: bool,
+}
+
+impl<P: CanvasPixel> CompositedFrame<'_, P> {
+    /// Copies the canvas if it is borrowed so the frame outlives the next decoded frame.
+    pub fn into_owned(self) -> CompositedFrame<'static, P> {
+        CompositedFrame {
+            image: Cow::Owned(self.image.into_owned()),
+            delay_time: self.delay_time,
+            disposal_method: self.disposal_method,
+            user_input: self.user_input,
+        }
+    }
+}
+
+/// Decodes and composites the frames of a gif one after another.
+///
+/// `next_frame` lends the reused canvas and never allocates a new one. The `Iterator`
+/// implementation has to hand out owned frames and thus copies the canvas for every frame.
+pub struct GIFFrames<'a, R: Read, P: CanvasPixel = RGB<u8>> {
+    decoder: GIFDecoder<'a, R>,
+    /// Created once the logical screen is known, i.e. with the first frame
+    compositor: Option<Compositor<P>>,
+}
+
+impl<'a, R: Read, P: CanvasPixel> GIFFrames<'a, R, P> {
+    pub fn new(decoder: GIFDecoder<'a, R>) -> Self {
+        GIFFrames {
+            decoder,
+            compositor: None,
+        }
+    }
+
+    /// Decodes the next frame and composites it onto the canvas.
+    ///
+    /// Returns `None` after the last frame. The returned image borrows the canvas which is
+    /// overwritten by the next call.
+    pub fn next_frame(&mut self) -> Result<Option<CompositedFrame<'_, P>>, GIFParseError> {
+        let Some(frame) = self.decoder.next_frame()? else {
+            return Ok(None);
+        };
+
+        let compositor = self.compositor.get_or_insert_with(|| {
+            Compositor::new(self.decoder.logical_dim(), self.decoder.background_color())
+        });
+
+        Ok(Some(CompositedFrame {
+            image: Cow::Borrowed(compositor.composite(&frame)),
+            delay_time: frame.delay_time,
+            disposal_method: frame.disposal_method,
+            user_input: frame.user_input,
+        }))
+    }
+}
+
+impl<R: Read, P: CanvasPixel + 'static> Iterator for GIFFrames<'_, R, P> {
+    type Item = Result<CompositedFrame<'static, P>, GIFParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame()
+            .map(|frame| frame.map(CompositedFrame::into_owned))
+            .transpose()
+    }
+}
+
+impl<'a, R: Read> GIFDecoder<'a, R> {
+    /// Turns the decoder into an iterator over the composited frames.
+    pub fn into_frames(self) -> GIFFrames<'a, R> {
+        GIFFrames::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufReader};
+
+    use crate::{
+        colors::rgba::RGBA, formats::gif::limits::DecoderLimits, generic_image::GenericImage,
+    };
+
+    use super::*;
+
+    #[test]
+    fn stream_frames_from_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test-assets/animated.gif");
+        let reader = BufReader::new(File::open(path).unwrap());
+
+        let frames = GIFDecoder::new(reader)
+            .into_frames()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let delays = frames.iter().map(|f| f.delay_time).collect::<Vec<_>>();
+        assert_eq!(delays, [10, 20, 30]);
+        assert_eq!(frames[1].disposal_method, DisposalMethod::RestoreBackground);
+        assert_eq!(*frames[1].image.get_pixel(1, 1), RGB([0, 0, 255]));
+        assert_eq!(*frames[2].image.get_pixel(1, 1), RGB([255, 255, 255]));
+    }
+
+    #[test]
+    fn stream_frames_reuse_canvas() {
+        let gif_data = include_bytes!("../../../test-assets/transparent.gif");
+        let mut frames: GIFFrames<_, RGBA<u8>> = GIFFrames::new(GIFDecoder::new(&gif_data[..]));
+
+        let first = frames.next_frame().unwrap().unwrap();
+        assert!(matches!(first.image, Cow::Borrowed(_)));
+        assert_eq!(*first.image.get_pixel(0, 0), RGBA([0, 0, 0, 0]));
+
+        let second = frames.next_frame().unwrap().unwrap();
+        assert_eq!(*second.image.get_pixel(0, 0), RGBA([0, 255, 0, 255]));
+        assert_eq!(*second.image.get_pixel(1, 1), RGBA([255, 0, 0, 255]));
+
+        assert!(frames.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn stream_ignores_total_limits() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        // the frames have 16, 4 and 2 pixels
+        let limits = DecoderLimits {
+            max_frames: 2,
+            max_decoded_bytes: 16,
+            ..DecoderLimits::default()
+        };
+
+        let decoder = GIFDecoder::new(&gif_data[..]).with_limits(limits);
+        assert!(decoder.decode_frames().is_err());
+
+        let decoder = GIFDecoder::new(&gif_data[..]).with_limits(limits);
+        let frames = decoder.into_frames().collect::<Result<Vec<_>, _>>();
+        assert_eq!(frames.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn stream_ends_after_error() {
+        let gif_data = include_bytes!("../../../test-assets/animated.gif");
+        let truncated = &gif_data[..gif_data.len() / 2];
+
+        // the frames before the truncated one, then a single error
+        let items = GIFDecoder::new(truncated)
+            .into_frames()
+            .take(10)
+            .collect::<Vec<_>>();
+        let (last, frames) = items.split_last().unwrap();
+        assert!(last.is_err());
+        assert!(frames.iter().all(Result::is_ok));
+    }
+}